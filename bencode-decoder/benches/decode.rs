@@ -0,0 +1,73 @@
+#![allow(clippy::needless_return)]
+
+use bencode_decoder::*;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+/// Multi-file torrent with `piece_count` piece hashes and `file_count` files.
+fn large_torrent(piece_count: usize, file_count: usize) -> Vec<u8> {
+    let mut ret = b"d8:announce32:http://tracker.example.com:6969/13:creation datei1700000000e4:infod5:filesl"
+        .to_vec();
+    for i in 0..file_count {
+        let name = format!("file-{:06}.bin", i);
+        ret.extend_from_slice(
+            format!(
+                "d6:lengthi{}e4:pathl3:dir{}:{}ee",
+                1000 + i,
+                name.len(),
+                name
+            )
+            .as_bytes(),
+        );
+    }
+    ret.extend_from_slice(b"e4:name5:large12:piece lengthi262144e6:pieces");
+    ret.extend_from_slice(format!("{}:", piece_count * 20).as_bytes());
+    ret.extend((0..piece_count * 20).map(|x| (x * 31 % 251) as u8));
+    ret.extend_from_slice(b"ee");
+    return ret;
+}
+
+fn nested_lists(depth: usize) -> Vec<u8> {
+    return ("l".repeat(depth) + &"e".repeat(depth)).into_bytes();
+}
+
+fn bench_large_torrent(c: &mut Criterion) {
+    let input = large_torrent(20_000, 5_000);
+    assert!(decode(&input).is_ok());
+
+    let mut group = c.benchmark_group("large_torrent");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("decode", |b| b.iter(|| decode(black_box(&input))));
+    group.bench_function("decode_borrowed", |b| {
+        b.iter(|| decode_borrowed(black_box(&input)))
+    });
+    group.bench_function("decode_from_reader", |b| {
+        b.iter(|| decode_from_reader(&mut black_box(&input[..])))
+    });
+    group.finish();
+}
+
+fn bench_nested(c: &mut Criterion) {
+    let depth = 10_000;
+    let input = nested_lists(depth);
+    let limits = DecodeLimits {
+        max_depth: usize::MAX,
+        ..Default::default()
+    };
+    assert!(decode_with_limits(&input, &limits).is_ok());
+
+    let mut group = c.benchmark_group("nested_lists");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("decode_with_limits", |b| {
+        b.iter(|| {
+            let mut element = decode_with_limits(black_box(&input), &limits).unwrap();
+            // Unwrap the lists one by one since dropping them at once would recurse.
+            while let Element::List(mut x) = element {
+                element = x.pop().unwrap_or(Element::Integer(0));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_large_torrent, bench_nested);
+criterion_main!(benches);
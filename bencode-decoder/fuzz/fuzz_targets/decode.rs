@@ -0,0 +1,30 @@
+#![no_main]
+
+use bencode_decoder::*;
+use libfuzzer_sys::fuzz_target;
+
+// No decoder may panic on arbitrary input, and whatever decodes must survive `encode`.
+fuzz_target!(|data: &[u8]| {
+    let decoded = decode(data);
+    if let Ok(element) = &decoded {
+        let encoded = encode(element);
+        assert_eq!(decode(&encoded).as_ref(), Ok(element));
+        assert_eq!(decode_strict(&encoded).as_ref(), Ok(element));
+    }
+
+    let limits = DecodeLimits {
+        max_depth: 8,
+        max_byte_string_len: 64,
+    };
+    let _ = decode_with_limits(data, &limits);
+    let _ = decode_strict(data);
+    let _ = decode_with_spans(data);
+    let _ = decode_many(data);
+    assert_eq!(decode_borrowed(data).map(|x| x.to_owned()), decoded.clone().ok());
+
+    let mut reader = data;
+    let streamed = decode_from_reader(&mut reader);
+    if let Ok(element) = decoded {
+        assert_eq!(streamed, Ok(element));
+    }
+});
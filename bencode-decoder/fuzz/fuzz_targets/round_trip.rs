@@ -0,0 +1,10 @@
+#![no_main]
+
+use bencode_decoder::*;
+use libfuzzer_sys::fuzz_target;
+
+// Every element survives `decode(encode(x))` byte for byte.
+fuzz_target!(|element: Element| {
+    let encoded = encode(&element);
+    assert_eq!(decode_strict(&encoded), Ok(element));
+});
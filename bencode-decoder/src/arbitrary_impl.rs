@@ -0,0 +1,59 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::Element;
+
+/// Deepest nesting generated by `Element::arbitrary`, which keeps it well below `DecodeLimits`.
+const MAX_ARBITRARY_DEPTH: usize = 8;
+
+fn arbitrary_element(u: &mut Unstructured<'_>, depth: usize) -> Result<Element> {
+    let max_kind = if depth >= MAX_ARBITRARY_DEPTH { 1 } else { 3 };
+    match u.int_in_range(0..=max_kind)? {
+        0 => return Ok(Element::ByteString(Vec::<u8>::arbitrary(u)?)),
+        1 => return Ok(Element::from_i128(i128::arbitrary(u)?)),
+        2 => {
+            let mut list = Vec::<Element>::new();
+            while !u.is_empty() && bool::arbitrary(u)? {
+                list.push(arbitrary_element(u, depth + 1)?);
+            }
+            return Ok(Element::List(list));
+        }
+        _ => {
+            let mut dict = BTreeMap::<Vec<u8>, Element>::new();
+            while !u.is_empty() && bool::arbitrary(u)? {
+                let key = Vec::<u8>::arbitrary(u)?;
+                dict.insert(key, arbitrary_element(u, depth + 1)?);
+            }
+            return Ok(Element::Dictionary(dict));
+        }
+    }
+}
+
+/// Generates only elements which the decoder can produce, so `Raw` never appears.
+impl<'a> Arbitrary<'a> for Element {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        return arbitrary_element(u, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod arbitrary_test {
+        use super::*;
+        use crate::{decode, encode};
+
+        #[test]
+        fn arbitrary_01() {
+            let seed: Vec<u8> = (0..4096u32).map(|x| (x * 31 % 251) as u8).collect();
+            let mut u = Unstructured::new(&seed);
+            while !u.is_empty() {
+                let element = Element::arbitrary(&mut u).unwrap();
+                assert_eq!(decode(&encode(&element)), Ok(element));
+            }
+        }
+    }
+}
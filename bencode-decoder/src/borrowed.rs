@@ -0,0 +1,269 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::{decode_bytestring_header, decode_integer, DecodeError, DecodeLimits, Element};
+
+/// Borrowed counterpart of `Element` whose byte strings point into the decoded buffer.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ElementRef<'a> {
+    ByteString(&'a [u8]),
+    Integer(i64),
+    BigInteger(i128),
+    List(Vec<ElementRef<'a>>),
+    Dictionary(BTreeMap<&'a [u8], ElementRef<'a>>),
+}
+
+impl ElementRef<'_> {
+    /// Copy borrowed data into an owned `Element`.
+    pub fn to_owned(&self) -> Element {
+        match self {
+            ElementRef::ByteString(x) => Element::ByteString(x.to_vec()),
+            ElementRef::Integer(x) => Element::Integer(*x),
+            ElementRef::BigInteger(x) => Element::BigInteger(*x),
+            ElementRef::List(x) => Element::List(x.iter().map(|y| y.to_owned()).collect()),
+            ElementRef::Dictionary(x) => Element::Dictionary(
+                x.iter()
+                    .map(|(key, val)| (key.to_vec(), val.to_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn decode_bytesstring<'a>(
+    bencode: &'a [u8],
+    offset: usize,
+    len: &mut usize,
+) -> Result<&'a [u8], DecodeError> {
+    let mut start_idx = 0;
+    let bytes_len = decode_bytestring_header(bencode, offset, &mut start_idx, false)?;
+    if bytes_len > bencode.len() - start_idx {
+        return Err(DecodeError::UnexpectedEof);
+    }
+
+    let end_idx = start_idx + bytes_len;
+    *len = end_idx;
+    return Ok(&bencode[start_idx..end_idx]);
+}
+
+fn decode_list<'a>(
+    bencode: &'a [u8],
+    offset: usize,
+    len: &mut usize,
+    limits: &DecodeLimits,
+    depth: usize,
+) -> Result<ElementRef<'a>, DecodeError> {
+    if depth >= limits.max_depth {
+        return Err(DecodeError::DepthLimitExceeded { offset });
+    }
+
+    let mut list = Vec::<ElementRef<'a>>::new();
+    let mut idx = 1;
+    while idx < bencode.len() && bencode[idx] != b'e' {
+        let mut list_len = 0;
+        let elem_in_list = decode_all(
+            &bencode[idx..],
+            offset + idx,
+            &mut list_len,
+            limits,
+            depth + 1,
+        )?;
+        idx += list_len;
+        list.push(elem_in_list);
+    }
+
+    if idx >= bencode.len() {
+        return Err(DecodeError::UnexpectedEof);
+    }
+    *len = idx + 1;
+    return Ok(ElementRef::List(list));
+}
+
+fn decode_dictionary<'a>(
+    bencode: &'a [u8],
+    offset: usize,
+    len: &mut usize,
+    limits: &DecodeLimits,
+    depth: usize,
+) -> Result<ElementRef<'a>, DecodeError> {
+    if depth >= limits.max_depth {
+        return Err(DecodeError::DepthLimitExceeded { offset });
+    }
+
+    let mut dict = BTreeMap::<&'a [u8], ElementRef<'a>>::new();
+    let mut idx = 1;
+    while idx < bencode.len() && bencode[idx] != b'e' {
+        let key_offset = offset + idx;
+        if !bencode[idx].is_ascii_digit() {
+            return Err(DecodeError::InvalidKey { offset: key_offset });
+        }
+        let mut key_len = 0;
+        let dict_key = decode_bytesstring(&bencode[idx..], key_offset, &mut key_len)?;
+        idx += key_len;
+        if idx >= bencode.len() {
+            return Err(DecodeError::UnexpectedEof);
+        }
+
+        let mut val_len = 0;
+        let dict_val = decode_all(
+            &bencode[idx..],
+            offset + idx,
+            &mut val_len,
+            limits,
+            depth + 1,
+        )?;
+        idx += val_len;
+        if dict.insert(dict_key, dict_val).is_some() {
+            return Err(DecodeError::DuplicateKey { offset: key_offset });
+        }
+    }
+
+    if idx >= bencode.len() {
+        return Err(DecodeError::UnexpectedEof);
+    }
+    *len = idx + 1;
+    return Ok(ElementRef::Dictionary(dict));
+}
+
+fn decode_all<'a>(
+    bencode: &'a [u8],
+    offset: usize,
+    len: &mut usize,
+    limits: &DecodeLimits,
+    depth: usize,
+) -> Result<ElementRef<'a>, DecodeError> {
+    if bencode.is_empty() {
+        return Err(DecodeError::UnexpectedEof);
+    }
+
+    match bencode[0] {
+        b'0'..=b'9' => {
+            return Ok(ElementRef::ByteString(decode_bytesstring(
+                bencode, offset, len,
+            )?));
+        }
+        b'i' => {
+            let int = decode_integer(bencode, offset, len, false)?;
+            match i64::try_from(int) {
+                Ok(x) => return Ok(ElementRef::Integer(x)),
+                Err(_) => return Ok(ElementRef::BigInteger(int)),
+            }
+        }
+        b'l' => {
+            return decode_list(bencode, offset, len, limits, depth);
+        }
+        b'd' => {
+            return decode_dictionary(bencode, offset, len, limits, depth);
+        }
+        _ => {
+            return Err(DecodeError::InvalidByte { offset });
+        }
+    }
+}
+
+/// Decode bencoded data without copying byte strings.
+/// The length of decoded data must be same as the length of input.
+///
+/// # Arguments
+/// * `bencode` - bencoded data **without** spaces. Every byte string of the result borrows from it.
+pub fn decode_borrowed(bencode: &[u8]) -> Option<ElementRef<'_>> {
+    let mut len = 0;
+    let ret = decode_all(bencode, 0, &mut len, &DecodeLimits::default(), 0).ok()?;
+    if len != bencode.len() {
+        return None;
+    }
+    return Some(ret);
+}
+
+/// Same as `decode_prefix`, but without copying byte strings like `decode_borrowed`.
+///
+/// # Arguments
+/// * `bencode` - bencoded data **without** spaces. Every byte string of the result borrows from it.
+pub fn decode_borrowed_prefix(bencode: &[u8]) -> Option<(ElementRef<'_>, &[u8])> {
+    let mut len = 0;
+    let ret = decode_all(bencode, 0, &mut len, &DecodeLimits::default(), 0).ok()?;
+    return Some((ret, &bencode[len..]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_len_check;
+
+    mod decode_borrowed_test {
+        use super::*;
+
+        fn helper(input: &str) {
+            let result = decode_borrowed(input.as_bytes()).map(|x| x.to_owned());
+            assert_eq!(result, decode_len_check(input.as_bytes()));
+        }
+
+        #[test]
+        fn decode_borrowed_01() {
+            for input in [
+                "0:",
+                "5:a cde",
+                "i-10e",
+                "li1e2:ablee",
+                "d1:a1:b1:bde1:cli1234e2:abee",
+            ] {
+                helper(input);
+            }
+        }
+
+        #[test]
+        fn decode_borrowed_02() {
+            for input in [
+                "",
+                "5:abcdef",
+                "i0123e",
+                "li1e",
+                "d1:a1:b",
+                "d1:ai1ei2ei3ee",
+            ] {
+                helper(input);
+            }
+        }
+
+        #[test]
+        fn decode_borrowed_03() {
+            let input = b"d6:pieces4:abcde";
+            let result = decode_borrowed(input).unwrap();
+            if let ElementRef::Dictionary(dict) = result {
+                if let Some(ElementRef::ByteString(pieces)) = dict.get(b"pieces".as_slice()) {
+                    assert_eq!(*pieces, b"abcd");
+                    assert!(std::ptr::eq(pieces.as_ptr(), input[11..].as_ptr()));
+                    return;
+                }
+            }
+            panic!("pieces is not decoded");
+        }
+
+        #[test]
+        fn decode_borrowed_04() {
+            assert_eq!(decode_borrowed("l".repeat(100000).as_bytes()), None);
+        }
+    }
+
+    mod decode_borrowed_prefix_test {
+        use super::*;
+
+        #[test]
+        fn decode_borrowed_prefix_01() {
+            let (element, rest) = decode_borrowed_prefix(b"li1e1:aei2e").unwrap();
+            assert_eq!(element.to_owned(), decode_len_check(b"li1e1:ae").unwrap());
+            assert_eq!(rest, b"i2e");
+
+            let (element, rest) = decode_borrowed_prefix(b"0:").unwrap();
+            assert_eq!(element, ElementRef::ByteString(b""));
+            assert_eq!(rest, b"");
+        }
+
+        #[test]
+        fn decode_borrowed_prefix_02() {
+            for input in ["", "e", "li1e", "3:ab"] {
+                assert_eq!(decode_borrowed_prefix(input.as_bytes()), None, "{}", input);
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{decode, Element};
+
+/// One step of [`DiffPath::path`]: a dictionary key or a list index.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DiffSegment {
+    Key(Vec<u8>),
+    Index(usize),
+}
+
+/// How two elements differ at [`DiffPath::path`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DiffKind {
+    /// The key is only in the dictionary of `self`.
+    OnlyInSelf,
+    /// The key is only in the dictionary of `other`.
+    OnlyInOther,
+    /// Lists of different lengths. Their common elements are compared as well.
+    LengthMismatch { len: usize, other_len: usize },
+    /// Different byte strings or integers, or elements of different types.
+    ValueMismatch,
+}
+
+/// A difference found by [`Element::diff`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DiffPath {
+    /// Keys and indices from the root to where the elements differ. Empty for the root itself.
+    pub path: Vec<DiffSegment>,
+    pub kind: DiffKind,
+}
+
+/// Render `path` like `info.files[1].length`, with keys as lossy UTF-8.
+impl fmt::Display for DiffPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "<root>")?;
+        }
+        for (i, segment) in self.path.iter().enumerate() {
+            match segment {
+                DiffSegment::Key(x) if i == 0 => write!(f, "{}", String::from_utf8_lossy(x))?,
+                DiffSegment::Key(x) => write!(f, ".{}", String::from_utf8_lossy(x))?,
+                DiffSegment::Index(x) => write!(f, "[{}]", x)?,
+            }
+        }
+        match &self.kind {
+            DiffKind::OnlyInSelf => return write!(f, ": only in self"),
+            DiffKind::OnlyInOther => return write!(f, ": only in other"),
+            DiffKind::LengthMismatch { len, other_len } => {
+                return write!(f, ": length {} != {}", len, other_len)
+            }
+            DiffKind::ValueMismatch => return write!(f, ": different values"),
+        }
+    }
+}
+
+impl Element {
+    /// Paths at which `self` and `other` differ, in key and index order.
+    /// Dictionaries and lists are compared entry by entry, so only the innermost differences are
+    /// reported. `Raw` is compared as the element it decodes to. The result is empty iff `self == other`
+    /// (up to `Raw`).
+    pub fn diff(&self, other: &Element) -> Vec<DiffPath> {
+        let mut ret = Vec::<DiffPath>::new();
+        diff_all(self, other, &mut Vec::<DiffSegment>::new(), &mut ret);
+        return ret;
+    }
+}
+
+fn push(ret: &mut Vec<DiffPath>, path: &[DiffSegment], kind: DiffKind) {
+    ret.push(DiffPath {
+        path: path.to_vec(),
+        kind,
+    });
+}
+
+/// The element which `Raw` bytes encode, or `None` for other elements and malformed bytes.
+fn decode_raw(element: &Element) -> Option<Element> {
+    match element {
+        Element::Raw(x) => return decode(x).ok(),
+        _ => return None,
+    }
+}
+
+fn diff_all(left: &Element, right: &Element, path: &mut Vec<DiffSegment>, ret: &mut Vec<DiffPath>) {
+    if let Some(x) = decode_raw(left) {
+        return diff_all(&x, right, path, ret);
+    }
+    if let Some(x) = decode_raw(right) {
+        return diff_all(left, &x, path, ret);
+    }
+    match (left, right) {
+        (Element::List(x), Element::List(y)) => {
+            if x.len() != y.len() {
+                let kind = DiffKind::LengthMismatch {
+                    len: x.len(),
+                    other_len: y.len(),
+                };
+                push(ret, path, kind);
+            }
+            for (i, (l, r)) in x.iter().zip(y).enumerate() {
+                path.push(DiffSegment::Index(i));
+                diff_all(l, r, path, ret);
+                path.pop();
+            }
+        }
+        (Element::Dictionary(x), Element::Dictionary(y)) => {
+            let mut keys: Vec<&Vec<u8>> = x.keys().chain(y.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                path.push(DiffSegment::Key(key.clone()));
+                match (x.get(key), y.get(key)) {
+                    (Some(l), Some(r)) => diff_all(l, r, path, ret),
+                    (Some(_), None) => push(ret, path, DiffKind::OnlyInSelf),
+                    (None, _) => push(ret, path, DiffKind::OnlyInOther),
+                }
+                path.pop();
+            }
+        }
+        _ if left != right => push(ret, path, DiffKind::ValueMismatch),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod diff_test {
+        use super::*;
+        use alloc::string::ToString;
+
+        #[test]
+        fn diff_01() {
+            let element = decode(b"d1:ali1ei2ee1:bd1:ci1eee").unwrap();
+            assert_eq!(element.diff(&element.clone()), Vec::new());
+            assert_eq!(
+                Element::Raw(b"i1e".to_vec()).diff(&Element::Integer(1)),
+                Vec::new()
+            );
+        }
+
+        #[test]
+        fn diff_02() {
+            let left = decode(b"d1:ali1ei2ee1:bd1:ci1ee1:x0:e").unwrap();
+            let right = decode(b"d1:ali1ei3ei4ee1:bd1:ci2ee1:y0:e").unwrap();
+            let diff = left.diff(&right);
+            let key = |x: &str| DiffSegment::Key(x.as_bytes().to_vec());
+            assert_eq!(
+                diff,
+                vec![
+                    DiffPath {
+                        path: vec![key("a")],
+                        kind: DiffKind::LengthMismatch {
+                            len: 2,
+                            other_len: 3
+                        },
+                    },
+                    DiffPath {
+                        path: vec![key("a"), DiffSegment::Index(1)],
+                        kind: DiffKind::ValueMismatch,
+                    },
+                    DiffPath {
+                        path: vec![key("b"), key("c")],
+                        kind: DiffKind::ValueMismatch,
+                    },
+                    DiffPath {
+                        path: vec![key("x")],
+                        kind: DiffKind::OnlyInSelf,
+                    },
+                    DiffPath {
+                        path: vec![key("y")],
+                        kind: DiffKind::OnlyInOther,
+                    },
+                ]
+            );
+            assert_eq!(diff[1].to_string(), "a[1]: different values");
+            assert_eq!(diff[2].to_string(), "b.c: different values");
+        }
+
+        #[test]
+        fn diff_03() {
+            // Different types differ as values, without looking inside.
+            let diff = decode(b"li1ee")
+                .unwrap()
+                .diff(&decode(b"d1:ai1ee").unwrap());
+            assert_eq!(
+                diff,
+                vec![DiffPath {
+                    path: Vec::new(),
+                    kind: DiffKind::ValueMismatch,
+                }]
+            );
+            assert_eq!(diff[0].to_string(), "<root>: different values");
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use alloc::string::String;
+use core::fmt;
+use core::fmt::Write;
+
+use crate::{encode, Element};
+
+/// Render `self` as canonical bencode text for logs.
+/// Bytes which are not printable ASCII (and `\` itself) are written as `\xNN`, so the output is not
+/// always valid bencode; use [`encode`] to get the actual bytes.
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in encode(self) {
+            if byte.is_ascii_graphic() && byte != b'\\' || byte == b' ' {
+                write!(f, "{}", byte as char)?;
+            } else {
+                write!(f, "\\x{:02x}", byte)?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Byte strings longer than this are shown as `<N bytes>` instead of hex by [`Element::pretty`].
+const MAX_PRETTY_HEX_LEN: usize = 32;
+
+fn pretty_bytes(bytes: &[u8], out: &mut String) {
+    if let Ok(x) = core::str::from_utf8(bytes) {
+        write!(out, "{:?}", x).unwrap();
+    } else if bytes.len() > MAX_PRETTY_HEX_LEN {
+        write!(out, "<{} bytes>", bytes.len()).unwrap();
+    } else {
+        out.push_str("0x");
+        for byte in bytes {
+            write!(out, "{:02x}", byte).unwrap();
+        }
+    }
+}
+
+fn pretty_all(element: &Element, indent: usize, depth: usize, out: &mut String) {
+    let pad = " ".repeat(indent * (depth + 1));
+    match element {
+        Element::ByteString(x) => pretty_bytes(x, out),
+        Element::Integer(x) => write!(out, "{}", x).unwrap(),
+        Element::BigInteger(x) => write!(out, "{}", x).unwrap(),
+        Element::List(x) if x.is_empty() => out.push_str("[]"),
+        Element::List(x) => {
+            out.push_str("[\n");
+            for val in x {
+                out.push_str(&pad);
+                pretty_all(val, indent, depth + 1, out);
+                out.push('\n');
+            }
+            out.push_str(&" ".repeat(indent * depth));
+            out.push(']');
+        }
+        Element::Dictionary(x) if x.is_empty() => out.push_str("{}"),
+        Element::Dictionary(x) => {
+            out.push_str("{\n");
+            for (key, val) in x {
+                out.push_str(&pad);
+                pretty_bytes(key, out);
+                out.push_str(": ");
+                pretty_all(val, indent, depth + 1, out);
+                out.push('\n');
+            }
+            out.push_str(&" ".repeat(indent * depth));
+            out.push('}');
+        }
+        Element::Raw(x) => write!(out, "<raw {} bytes>", x.len()).unwrap(),
+    }
+}
+
+impl Element {
+    /// Render `self` as an indented tree for inspecting its structure, `indent` spaces per level.
+    /// Byte strings are shown quoted when valid UTF-8, as hex when short and as `<N bytes>` otherwise.
+    pub fn pretty(&self, indent: usize) -> String {
+        let mut ret = String::new();
+        pretty_all(self, indent, 0, &mut ret);
+        return ret;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decode;
+
+    mod display_test {
+        use super::*;
+
+        fn helper(input: &[u8], expect: &str) {
+            let element = decode(input).unwrap();
+            assert_eq!(element.to_string(), expect);
+        }
+
+        #[test]
+        fn display_01() {
+            helper(b"d1:ai-10e1:bl4:spam0:ee", "d1:ai-10e1:bl4:spam0:ee");
+        }
+
+        #[test]
+        fn display_02() {
+            helper(b"3:\x00\xff\\", "3:\\x00\\xff\\x5c");
+        }
+
+        #[test]
+        fn display_03() {
+            helper(b"11:hello world", "11:hello world");
+        }
+    }
+
+    mod pretty_test {
+        use super::*;
+
+        fn helper(input: &[u8], indent: usize, expect: &str) {
+            let element = decode(input).unwrap();
+            assert_eq!(element.pretty(indent), expect);
+        }
+
+        #[test]
+        fn pretty_01() {
+            helper(b"i-10e", 2, "-10");
+            helper(b"le", 2, "[]");
+            helper(b"de", 2, "{}");
+        }
+
+        #[test]
+        fn pretty_02() {
+            helper(
+                b"d1:ai1e1:bl4:spamd1:c0:eee",
+                2,
+                "{\n  \"a\": 1\n  \"b\": [\n    \"spam\"\n    {\n      \"c\": \"\"\n    }\n  ]\n}",
+            );
+        }
+
+        #[test]
+        fn pretty_03() {
+            helper(b"2:\xff\x00", 2, "0xff00");
+            let long = [&b"40:"[..], &[0xffu8; 40]].concat();
+            helper(&long, 2, "<40 bytes>");
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+/// Why and where decoding bencode failed.
+/// `offset` is the index of the byte in the input where decoding went wrong.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DecodeError {
+    /// Input ended in the middle of an element.
+    UnexpectedEof,
+    /// A byte which can not appear here, e.g. a byte starting no element or a missing `e`.
+    InvalidByte { offset: usize },
+    /// Malformed integer or byte-string length, e.g. no digits, `-0` or overflow.
+    InvalidInteger { offset: usize },
+    /// Integer or byte-string length too large, i.e. out of the range of `i128` or `usize`.
+    IntegerOverflow { offset: usize },
+    /// Integer or byte-string length with a redundant leading zero like `i03e`.
+    LeadingZero { offset: usize },
+    /// Byte-string length not followed by `:`.
+    ExpectedColon { offset: usize },
+    /// Dictionary key which is not a byte string.
+    InvalidKey { offset: usize },
+    /// Dictionary key appearing more than once in the same dictionary.
+    DuplicateKey { offset: usize },
+    /// Dictionary key smaller than the previous one in strict mode (`decode_strict`).
+    UnsortedKey { key: Vec<u8>, offset: usize },
+    /// Bytes remaining after the top-level element.
+    TrailingData { offset: usize },
+    /// List or dictionary nested deeper than `DecodeLimits::max_depth`.
+    DepthLimitExceeded { offset: usize },
+    /// Byte string whose declared length is above `DecodeLimits::max_byte_string_len`.
+    ByteStringTooLong { offset: usize },
+    /// Reading the input failed (only from `decode_from_reader`).
+    #[cfg(feature = "std")]
+    Io(io::ErrorKind),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEof => write!(f, "unexpected end of input"),
+            DecodeError::InvalidByte { offset } => write!(f, "invalid byte at offset {}", offset),
+            DecodeError::InvalidInteger { offset } => {
+                write!(f, "invalid integer at offset {}", offset)
+            }
+            DecodeError::IntegerOverflow { offset } => {
+                write!(f, "integer overflow at offset {}", offset)
+            }
+            DecodeError::LeadingZero { offset } => {
+                write!(f, "integer with leading zero at offset {}", offset)
+            }
+            DecodeError::ExpectedColon { offset } => write!(f, "expected ':' at offset {}", offset),
+            DecodeError::InvalidKey { offset } => {
+                write!(f, "invalid dictionary key at offset {}", offset)
+            }
+            DecodeError::DuplicateKey { offset } => {
+                write!(f, "duplicate dictionary key at offset {}", offset)
+            }
+            DecodeError::UnsortedKey { key, offset } => write!(
+                f,
+                "dictionary key {:?} out of order at offset {}",
+                String::from_utf8_lossy(key),
+                offset
+            ),
+            DecodeError::TrailingData { offset } => {
+                write!(f, "trailing data at offset {}", offset)
+            }
+            DecodeError::DepthLimitExceeded { offset } => {
+                write!(f, "nesting depth limit exceeded at offset {}", offset)
+            }
+            DecodeError::ByteStringTooLong { offset } => {
+                write!(f, "byte string length limit exceeded at offset {}", offset)
+            }
+            #[cfg(feature = "std")]
+            DecodeError::Io(kind) => write!(f, "I/O error: {}", kind),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
@@ -1,15 +1,53 @@
+#![allow(clippy::needless_return)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Index;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
+mod borrowed;
+mod diff;
+mod display;
+mod error;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod spans;
 mod utils;
 
+pub use crate::borrowed::{decode_borrowed, decode_borrowed_prefix, ElementRef};
+pub use crate::diff::{DiffKind, DiffPath, DiffSegment};
+pub use crate::error::DecodeError;
+#[cfg(feature = "std")]
+pub use crate::reader::{decode_from_reader, decode_from_reader_with_limits};
+#[cfg(feature = "serde")]
+pub use crate::serde_impl::BASE64_PREFIX;
+pub use crate::spans::{decode_with_spans, Span, SpanMap};
 use crate::utils::*;
 
+/// Decoded bencode element.
+/// Dictionaries are kept in sorted key order, so encoding an element is deterministic and canonical.
+/// Keys are raw byte strings because bencode does not require them to be UTF-8; use [`Element::get`] to look them up by `&str`.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Element {
     ByteString(Vec<u8>),
     Integer(i64),
+    /// Integer which does not fit in `i64`. The decoder produces `Integer` whenever the value fits,
+    /// so build this through `Element::from_i128` to keep equal values equal.
+    BigInteger(i128),
     List(Vec<Element>),
-    Dictionary(HashMap<String, Element>),
+    Dictionary(BTreeMap<Vec<u8>, Element>),
+    /// Already bencoded bytes which are emitted verbatim by [`encode`].
+    /// The decoder never produces this variant.
+    Raw(Vec<u8>),
 }
 
 #[allow(dead_code)]
@@ -22,6 +60,15 @@ impl Element {
         }
     }
 
+    /// Same as `convert_to_ref_vec_u8`, but as a slice.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        if let Element::ByteString(x) = self {
+            Some(x)
+        } else {
+            None
+        }
+    }
+
     pub fn convert_to_str(&self) -> Option<&str> {
         if let Element::ByteString(x) = self {
             core::str::from_utf8(x).ok()
@@ -42,17 +89,114 @@ impl Element {
         }
     }
 
+    /// `Integer` if `x` fits in `i64`, otherwise `BigInteger`.
+    pub fn from_i128(x: i128) -> Element {
+        match i64::try_from(x) {
+            Ok(y) => return Element::Integer(y),
+            Err(_) => return Element::BigInteger(x),
+        }
+    }
+
+    /// Value of either `Integer` or `BigInteger`.
+    pub fn convert_to_i128(&self) -> Option<i128> {
+        match self {
+            Element::Integer(x) => Some(i128::from(*x)),
+            Element::BigInteger(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// Bencode has no boolean type, so flags like `private` are `i1e` for true and `i0e` for false.
+    /// `None` for any other integer.
+    pub fn convert_to_bool(&self) -> Option<bool> {
+        match self.convert_to_i64()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Canonical decimal digits of an integer, which is what appears between `i` and `e` in bencode.
+    pub fn integer_to_ascii(&self) -> Option<Vec<u8>> {
+        Some(self.convert_to_i128()?.to_string().into_bytes())
+    }
+
+    /// `None` if the integer is negative.
     pub fn convert_to_u64(&self) -> Option<u64> {
-        if let Some(x) = self.convert_to_i64() {
-            Some(x as u64)
+        u64::try_from(self.convert_to_i64()?).ok()
+    }
+
+    /// `None` if the integer does not fit in `usize`.
+    pub fn convert_to_usize(&self) -> Option<usize> {
+        usize::try_from(self.convert_to_i64()?).ok()
+    }
+
+    /// `None` if the integer does not fit in `u32`.
+    pub fn convert_to_u32(&self) -> Option<u32> {
+        u32::try_from(self.convert_to_i64()?).ok()
+    }
+
+    /// `None` if the integer does not fit in `u16`.
+    pub fn convert_to_u16(&self) -> Option<u16> {
+        u16::try_from(self.convert_to_i64()?).ok()
+    }
+
+    /// `None` if the integer does not fit in `i32`.
+    pub fn convert_to_i32(&self) -> Option<i32> {
+        i32::try_from(self.convert_to_i64()?).ok()
+    }
+
+    pub fn convert_to_string_list(&self) -> Option<Vec<String>> {
+        if let Element::List(x) = self {
+            x.iter().map(|y| y.convert_to_string()).collect()
         } else {
             None
         }
     }
 
-    pub fn convert_to_string_list(&self) -> Option<Vec<String>> {
+    /// Same as `convert_to_string_list`, but without requiring UTF-8, e.g. for a list of raw hashes.
+    pub fn convert_to_bytes_list(&self) -> Option<Vec<Vec<u8>>> {
         if let Element::List(x) = self {
-            x.iter().map(|y| y.convert_to_string()).collect()
+            x.iter().map(|y| y.as_bytes().map(<[u8]>::to_vec)).collect()
+        } else {
+            None
+        }
+    }
+
+    pub fn iter_list(&self) -> Option<core::slice::Iter<'_, Element>> {
+        return Some(self.convert_to_ref_list()?.iter());
+    }
+
+    pub fn iter_list_mut(&mut self) -> Option<core::slice::IterMut<'_, Element>> {
+        if let Element::List(x) = self {
+            Some(x.iter_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Move the bytes out of `self` instead of cloning them.
+    pub fn into_byte_string(self) -> Option<Vec<u8>> {
+        if let Element::ByteString(x) = self {
+            Some(x)
+        } else {
+            None
+        }
+    }
+
+    /// Move the list out of `self` instead of cloning it.
+    pub fn into_list(self) -> Option<Vec<Element>> {
+        if let Element::List(x) = self {
+            Some(x)
+        } else {
+            None
+        }
+    }
+
+    /// Move the dictionary out of `self` instead of cloning it.
+    pub fn into_dict(self) -> Option<BTreeMap<Vec<u8>, Element>> {
+        if let Element::Dictionary(x) = self {
+            Some(x)
         } else {
             None
         }
@@ -67,13 +211,54 @@ impl Element {
     }
 
     pub fn convert_to_list(&self) -> Option<Vec<Element>> {
-        match self.convert_to_ref_list() {
-            Some(x) => Some(x.clone()),
-            None => None,
+        self.convert_to_ref_list().cloned()
+    }
+
+    /// Parse a list of `[host, port]` pairs like the `nodes` key of trackerless torrents (BEP 5).
+    /// `host` must be a UTF-8 byte string and `port` either an integer or 2 bytes of big-endian port.
+    pub fn as_host_port_list(&self) -> Option<Vec<(String, u16)>> {
+        let mut ret = Vec::<(String, u16)>::new();
+        for pair in self.convert_to_ref_list()? {
+            let pair = pair.convert_to_ref_list()?;
+            if pair.len() != 2 {
+                return None;
+            }
+
+            let host = pair[0].convert_to_string()?;
+            let port = match &pair[1] {
+                Element::Integer(x) => u16::try_from(*x).ok()?,
+                Element::ByteString(x) if x.len() == 2 => u16::from_be_bytes([x[0], x[1]]),
+                _ => return None,
+            };
+            ret.push((host, port));
+        }
+        return Some(ret);
+    }
+
+    /// Look up `key` if `self` is a dictionary.
+    pub fn get(&self, key: &str) -> Option<&Element> {
+        return self.convert_to_ref_dict()?.get(key.as_bytes());
+    }
+
+    /// Get `index`-th element if `self` is a list.
+    pub fn get_index(&self, index: usize) -> Option<&Element> {
+        return self.convert_to_ref_list()?.get(index);
+    }
+
+    /// Walk nested dictionaries (and lists) following `path`, e.g. `element.path(&["info", "files"])`.
+    /// Returns `None` at the first missing segment or type mismatch.
+    pub fn path<'a, S: Into<PathSegment<'a>> + Copy>(&self, path: &[S]) -> Option<&Element> {
+        let mut ret = self;
+        for segment in path {
+            ret = match (*segment).into() {
+                PathSegment::Key(key) => ret.get(key)?,
+                PathSegment::Index(index) => ret.get_index(index)?,
+            };
         }
+        return Some(ret);
     }
 
-    pub fn convert_to_ref_dict(&self) -> Option<&HashMap<String, Element>> {
+    pub fn convert_to_ref_dict(&self) -> Option<&BTreeMap<Vec<u8>, Element>> {
         if let Element::Dictionary(x) = self {
             Some(x)
         } else {
@@ -81,137 +266,488 @@ impl Element {
         }
     }
 
-    pub fn convert_to_dict(&self) -> Option<HashMap<String, Element>> {
-        match self.convert_to_ref_dict() {
-            Some(x) => Some(x.clone()),
-            None => None,
+    pub fn convert_to_dict(&self) -> Option<BTreeMap<Vec<u8>, Element>> {
+        self.convert_to_ref_dict().cloned()
+    }
+
+    /// The number of bytes of a byte string or entries of a list or dictionary.
+    /// `None` for integers and `Raw`.
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Element::ByteString(x) => Some(x.len()),
+            Element::List(x) => Some(x.len()),
+            Element::Dictionary(x) => Some(x.len()),
+            Element::Integer(_) | Element::BigInteger(_) | Element::Raw(_) => None,
         }
     }
+
+    /// Whether `len` is 0. `None` for integers and `Raw`.
+    pub fn is_empty(&self) -> Option<bool> {
+        Some(self.len()? == 0)
+    }
 }
 
-fn decode_bytesstring(bencode: &[u8], len: &mut usize) -> Option<Element> {
-    if bencode.len() == 0 {
-        *len = 0;
-        return None;
+/// One step of [`Element::path`]: a dictionary key or a list index.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+impl<'a> From<&'a str> for PathSegment<'a> {
+    fn from(key: &'a str) -> Self {
+        return PathSegment::Key(key);
     }
+}
 
-    let mut bytes_len_len = 0;
-    let bytes_len = decode_u64(&bencode[0..], &mut bytes_len_len)? as usize;
-    let start_idx = bytes_len_len + 1;
-    let end_idx = start_idx + bytes_len;
-    if start_idx > bencode.len() || bencode[bytes_len_len] != b':' || end_idx > bencode.len() {
-        return None;
+impl From<usize> for PathSegment<'_> {
+    fn from(index: usize) -> Self {
+        return PathSegment::Index(index);
     }
+}
 
-    let bytes = &bencode[start_idx..(end_idx)];
-    *len = end_idx;
-    return Some(Element::ByteString(bytes.to_vec()));
+/// Index into a dictionary by key.
+/// Panics if `self` is not a dictionary or `key` is missing; use [`Element::get`] otherwise.
+impl Index<&str> for Element {
+    type Output = Element;
+
+    fn index(&self, key: &str) -> &Element {
+        match self.get(key) {
+            Some(x) => return x,
+            None => panic!("no dictionary key {:?} in bencode element", key),
+        }
+    }
 }
 
-fn decode_integer(bencode: &[u8], len: &mut usize) -> Option<Element> {
-    if bencode.len() < 3 || bencode[0] != b'i' {
-        *len = 0;
-        return None;
+/// Index into a list.
+/// Panics if `self` is not a list or `index` is out of bounds; use [`Element::get_index`] otherwise.
+impl Index<usize> for Element {
+    type Output = Element;
+
+    fn index(&self, index: usize) -> &Element {
+        match self.get_index(index) {
+            Some(x) => return x,
+            None => panic!("no list index {} in bencode element", index),
+        }
     }
+}
 
-    let mut int_len = 0;
-    let int = decode_i64(&bencode[1..], &mut int_len)?;
-    if 1 + int_len >= bencode.len() || bencode[1 + int_len] != b'e' {
-        return None;
+impl From<i64> for Element {
+    fn from(x: i64) -> Self {
+        return Element::Integer(x);
     }
-    *len = int_len + 2;
-    return Some(Element::Integer(int));
 }
 
-fn decode_list(bencode: &[u8], len: &mut usize) -> Option<Element> {
-    if bencode.len() < 2 || bencode[0] != b'l' {
-        *len = 0;
-        return None;
+impl From<&str> for Element {
+    fn from(x: &str) -> Self {
+        return Element::ByteString(x.as_bytes().to_vec());
     }
+}
 
-    let mut list = Vec::<Element>::new();
-    let mut idx = 1;
-    while idx < bencode.len() && bencode[idx] != b'e' {
-        let mut list_len = 0;
-        let elem_in_list = decode_all(&bencode[idx..], &mut list_len)?;
-        idx += list_len;
-        list.push(elem_in_list);
+impl From<String> for Element {
+    fn from(x: String) -> Self {
+        return Element::ByteString(x.into_bytes());
     }
+}
 
-    if bencode[idx] != b'e' {
-        *len = idx;
-        return None;
+impl From<Vec<u8>> for Element {
+    fn from(x: Vec<u8>) -> Self {
+        return Element::ByteString(x);
     }
-    *len = idx + 1;
-    return Some(Element::List(list));
 }
 
-fn decode_dictionary(bencode: &[u8], len: &mut usize) -> Option<Element> {
-    if bencode.len() < 2 || bencode[0] != b'd' {
-        *len = 0;
-        return None;
+impl From<Vec<Element>> for Element {
+    fn from(x: Vec<Element>) -> Self {
+        return Element::List(x);
     }
+}
 
-    let mut dict = HashMap::<String, Element>::new();
-    let mut idx = 1;
-    while idx < bencode.len() && bencode[idx] != b'e' {
-        let mut key_len = 0;
-        let dict_key = decode_bytesstring(&bencode[idx..], &mut key_len)?.convert_to_string()?;
-        idx += key_len;
-        if idx >= bencode.len() {
-            return None;
-        }
+impl From<BTreeMap<Vec<u8>, Element>> for Element {
+    fn from(x: BTreeMap<Vec<u8>, Element>) -> Self {
+        return Element::Dictionary(x);
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<HashMap<String, Element>> for Element {
+    fn from(x: HashMap<String, Element>) -> Self {
+        return Element::Dictionary(
+            x.into_iter()
+                .map(|(key, val)| (key.into_bytes(), val))
+                .collect(),
+        );
+    }
+}
 
-        let mut val_len = 0;
-        let dict_val = decode_all(&bencode[idx..], &mut val_len)?;
-        idx += val_len;
-        dict.insert(dict_key, dict_val);
+/// Decode `<length>:` at the beginning of a byte string and return `length`.
+/// `header_len` is set to the length of `<length>:`.
+/// If `lenient` is true, `length` may have leading zeros.
+pub(crate) fn decode_bytestring_header(
+    bencode: &[u8],
+    offset: usize,
+    header_len: &mut usize,
+    lenient: bool,
+) -> Result<usize, DecodeError> {
+    if bencode.is_empty() {
+        *header_len = 0;
+        return Err(DecodeError::UnexpectedEof);
     }
 
-    if bencode[idx] != b'e' {
-        return None;
+    let mut bytes_len_len = 0;
+    let bytes_len = match decode_u128_checked(&bencode[0..], &mut bytes_len_len, lenient) {
+        Ok(x) => match usize::try_from(x) {
+            Ok(y) => y,
+            Err(_) => return Err(DecodeError::IntegerOverflow { offset }),
+        },
+        Err(NumberError::Overflow) => return Err(DecodeError::IntegerOverflow { offset }),
+        Err(_) => return Err(DecodeError::InvalidInteger { offset }),
+    };
+    if bytes_len_len >= bencode.len() {
+        return Err(DecodeError::UnexpectedEof);
+    } else if bytes_len == 0 && bencode[bytes_len_len].is_ascii_digit() {
+        return Err(DecodeError::LeadingZero { offset });
+    } else if bencode[bytes_len_len] != b':' {
+        return Err(DecodeError::ExpectedColon {
+            offset: offset + bytes_len_len,
+        });
+    }
+
+    *header_len = bytes_len_len + 1;
+    return Ok(bytes_len);
+}
+
+fn decode_bytesstring(
+    bencode: &[u8],
+    offset: usize,
+    len: &mut usize,
+    options: &DecodeOptions,
+) -> Result<Element, DecodeError> {
+    let mut start_idx = 0;
+    let bytes_len = decode_bytestring_header(bencode, offset, &mut start_idx, options.lenient)?;
+    if bytes_len > options.limits.max_byte_string_len {
+        return Err(DecodeError::ByteStringTooLong { offset });
+    } else if bytes_len > bencode.len() - start_idx {
+        return Err(DecodeError::UnexpectedEof);
     }
-    *len = idx + 1;
-    return Some(Element::Dictionary(dict));
+
+    let end_idx = start_idx + bytes_len;
+    let bytes = &bencode[start_idx..(end_idx)];
+    *len = end_idx;
+    return Ok(Element::ByteString(bytes.to_vec()));
 }
 
-fn decode_all(bencode: &[u8], len: &mut usize) -> Option<Element> {
-    if bencode.len() == 0 {
-        return None;
+/// If `lenient` is true, the integer may start with `+`, have leading zeros or be `-0`.
+pub(crate) fn decode_integer(
+    bencode: &[u8],
+    offset: usize,
+    len: &mut usize,
+    lenient: bool,
+) -> Result<i128, DecodeError> {
+    if bencode.len() < 2 {
+        *len = 0;
+        return Err(DecodeError::UnexpectedEof);
     }
 
-    match bencode[0] {
-        b'0'..=b'9' => {
-            return decode_bytesstring(bencode, len);
+    let mut int_len = 0;
+    let int = decode_i128_checked(&bencode[1..], &mut int_len, lenient);
+    if 1 + int_len >= bencode.len() {
+        return Err(DecodeError::UnexpectedEof);
+    }
+    let int = match int {
+        Ok(x) => x,
+        Err(NumberError::Overflow) => {
+            return Err(DecodeError::IntegerOverflow { offset: offset + 1 })
         }
-        b'i' => {
-            return decode_integer(bencode, len);
+        Err(_) => return Err(DecodeError::InvalidInteger { offset: offset + 1 }),
+    };
+    if bencode[1 + int_len] != b'e' {
+        if int == 0 && bencode[1 + int_len].is_ascii_digit() {
+            return Err(DecodeError::LeadingZero { offset: offset + 1 });
+        }
+        return Err(DecodeError::InvalidInteger {
+            offset: offset + 1 + int_len,
+        });
+    }
+    *len = int_len + 2;
+    return Ok(int);
+}
+
+/// List or dictionary which is being decoded by `decode_all`.
+enum Frame {
+    List(Vec<Element>),
+    Dictionary {
+        dict: BTreeMap<Vec<u8>, Element>,
+        /// Key (and its offset) whose value is decoded next, `None` while a key is expected.
+        key: Option<(Vec<u8>, usize)>,
+        prev_key: Option<Vec<u8>>,
+    },
+}
+
+/// Decode an element at the beginning of `bencode`; `len` is set to the number of bytes it takes.
+/// Nested lists and dictionaries are kept on an explicit stack instead of recursing, so hostile
+/// nesting is bounded by `options.limits` and heap memory but never overflows the call stack.
+fn decode_all(
+    bencode: &[u8],
+    offset: usize,
+    len: &mut usize,
+    options: &DecodeOptions,
+    depth: usize,
+) -> Result<Element, DecodeError> {
+    let mut stack = Vec::<Frame>::new();
+    let mut idx = 0;
+    loop {
+        if idx >= bencode.len() {
+            *len = idx;
+            return Err(DecodeError::UnexpectedEof);
         }
-        b'l' => {
-            return decode_list(bencode, len);
+
+        let mut element = None;
+        match stack.last_mut() {
+            Some(Frame::List(_)) | Some(Frame::Dictionary { key: None, .. })
+                if bencode[idx] == b'e' =>
+            {
+                idx += 1;
+                element = match stack.pop() {
+                    Some(Frame::List(x)) => Some(Element::List(x)),
+                    Some(Frame::Dictionary { dict, .. }) => Some(Element::Dictionary(dict)),
+                    None => None,
+                };
+            }
+            Some(Frame::Dictionary {
+                key: key @ None,
+                prev_key,
+                ..
+            }) => {
+                let key_offset = offset + idx;
+                if !bencode[idx].is_ascii_digit() {
+                    return Err(DecodeError::InvalidKey { offset: key_offset });
+                }
+                let mut key_len = 0;
+                let dict_key =
+                    match decode_bytesstring(&bencode[idx..], key_offset, &mut key_len, options)? {
+                        Element::ByteString(x) => x,
+                        _ => return Err(DecodeError::InvalidKey { offset: key_offset }),
+                    };
+                idx += key_len;
+                if options.sorted_keys {
+                    if prev_key.as_ref().is_some_and(|x| x > &dict_key) {
+                        return Err(DecodeError::UnsortedKey {
+                            key: dict_key,
+                            offset: key_offset,
+                        });
+                    }
+                    *prev_key = Some(dict_key.clone());
+                }
+                *key = Some((dict_key, key_offset));
+                continue;
+            }
+            _ => (),
         }
-        b'd' => {
-            return decode_dictionary(bencode, len);
+
+        let element = match element {
+            Some(x) => x,
+            None => {
+                let mut elem_len = 0;
+                match bencode[idx] {
+                    b'0'..=b'9' => {
+                        let ret = decode_bytesstring(
+                            &bencode[idx..],
+                            offset + idx,
+                            &mut elem_len,
+                            options,
+                        )?;
+                        idx += elem_len;
+                        ret
+                    }
+                    b'i' => {
+                        let ret = decode_integer(
+                            &bencode[idx..],
+                            offset + idx,
+                            &mut elem_len,
+                            options.lenient,
+                        )?;
+                        idx += elem_len;
+                        Element::from_i128(ret)
+                    }
+                    b'l' | b'd' => {
+                        if depth + stack.len() >= options.limits.max_depth {
+                            return Err(DecodeError::DepthLimitExceeded {
+                                offset: offset + idx,
+                            });
+                        }
+                        stack.push(if bencode[idx] == b'l' {
+                            Frame::List(Vec::new())
+                        } else {
+                            Frame::Dictionary {
+                                dict: BTreeMap::new(),
+                                key: None,
+                                prev_key: None,
+                            }
+                        });
+                        idx += 1;
+                        continue;
+                    }
+                    _ => {
+                        return Err(DecodeError::InvalidByte {
+                            offset: offset + idx,
+                        });
+                    }
+                }
+            }
+        };
+
+        match stack.last_mut() {
+            None => {
+                *len = idx;
+                return Ok(element);
+            }
+            Some(Frame::List(list)) => list.push(element),
+            Some(Frame::Dictionary { dict, key, .. }) => {
+                let (dict_key, key_offset) = key.take().unwrap();
+                if dict.insert(dict_key, element).is_some() {
+                    return Err(DecodeError::DuplicateKey { offset: key_offset });
+                }
+            }
         }
-        b'e' | _ => {
-            return None;
+    }
+}
+
+/// Limits applied while decoding untrusted bencode.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DecodeLimits {
+    /// Maximum nesting depth of lists and dictionaries. The top-level list or dictionary has depth 1.
+    pub max_depth: usize,
+    /// Maximum declared length of a byte string, checked before anything is read or allocated.
+    pub max_byte_string_len: usize,
+}
+
+impl DecodeLimits {
+    pub const DEFAULT_MAX_DEPTH: usize = 100;
+    pub const DEFAULT_MAX_BYTE_STRING_LEN: usize = 64 * 1024 * 1024;
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DecodeLimits {
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            max_byte_string_len: Self::DEFAULT_MAX_BYTE_STRING_LEN,
         }
     }
 }
 
+/// How the decoder behaves, shared by `decode_all` and its helpers.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub(crate) struct DecodeOptions {
+    pub limits: DecodeLimits,
+    /// Reject dictionaries whose keys are not in sorted order (BEP 3).
+    pub sorted_keys: bool,
+    /// Accept the malformed numbers described in `decode_lenient`.
+    pub lenient: bool,
+}
+
+fn decode_with_options(bencode: &[u8], options: &DecodeOptions) -> Result<Element, DecodeError> {
+    let mut len = 0;
+    let ret = decode_all(bencode, 0, &mut len, options, 0)?;
+    if len != bencode.len() {
+        return Err(DecodeError::TrailingData { offset: len });
+    }
+    return Ok(ret);
+}
+
+/// Decode bencoded data.
+/// The input must consist of exactly one element; otherwise `DecodeError::TrailingData` is returned.
+/// Nesting is limited by `DecodeLimits::default()`.
+/// Dictionary keys may appear in any order; use `decode_strict` to require sorted keys.
+///
+/// # Arguments
+/// * `bencode` - bencoded data **without** spaces. Use `decode_trim` to ignore surrounding whitespace.
+pub fn decode(bencode: &[u8]) -> Result<Element, DecodeError> {
+    return decode_with_limits(bencode, &DecodeLimits::default());
+}
+
+/// Same as `decode`, but with the given `limits` instead of the default ones.
+///
+/// # Arguments
+/// * `bencode` - bencoded data **without** spaces.
+/// * `limits` - limits which make decoding fail with an error when exceeded.
+pub fn decode_with_limits(bencode: &[u8], limits: &DecodeLimits) -> Result<Element, DecodeError> {
+    let options = DecodeOptions {
+        limits: *limits,
+        ..Default::default()
+    };
+    return decode_with_options(bencode, &options);
+}
+
+/// Same as `decode`, but keys of every dictionary must be in sorted raw byte order as BEP 3 requires.
+/// This validates that data was produced by a compliant encoder; an out of order key fails with
+/// `DecodeError::UnsortedKey`.
+///
+/// # Arguments
+/// * `bencode` - bencoded data **without** spaces.
+pub fn decode_strict(bencode: &[u8]) -> Result<Element, DecodeError> {
+    let options = DecodeOptions {
+        sorted_keys: true,
+        ..Default::default()
+    };
+    return decode_with_options(bencode, &options);
+}
+
+/// Same as `decode`, but tolerates numbers emitted by some buggy encoders:
+/// - integers starting with `+` (`i+5e`),
+/// - integers with leading zeros (`i007e`, `i-05e`),
+/// - negative zero (`i-0e`, `i-00e`),
+/// - byte string lengths with leading zeros (`03:abc`).
+///
+/// Every other deviation is still an error. Decoded numbers are normalized, so `encode` of the
+/// result is canonical and differs from the input wherever one of the above was tolerated.
+///
+/// # Arguments
+/// * `bencode` - bencoded data **without** spaces.
+pub fn decode_lenient(bencode: &[u8]) -> Result<Element, DecodeError> {
+    let options = DecodeOptions {
+        lenient: true,
+        ..Default::default()
+    };
+    return decode_with_options(bencode, &options);
+}
+
+/// Same as `decode`, but ASCII whitespace before and after the element is ignored, e.g. a trailing
+/// newline of bencode pasted from a text dump.
+/// Whitespace inside the element is not removed: bencode has no separators between tokens, so it is
+/// either part of a byte string, which must be kept as it is, or an error like in `decode`.
+/// Offsets of errors are those in `bencode` including the leading whitespace.
+///
+/// # Arguments
+/// * `bencode` - bencoded data, optionally surrounded by whitespace.
+pub fn decode_trim(bencode: &[u8]) -> Result<Element, DecodeError> {
+    let start = bencode
+        .iter()
+        .position(|x| !x.is_ascii_whitespace())
+        .unwrap_or(bencode.len());
+    let mut len = 0;
+    let ret = decode_all(
+        &bencode[start..],
+        start,
+        &mut len,
+        &DecodeOptions::default(),
+        0,
+    )?;
+    let end = start + len;
+    if let Some(x) = bencode[end..].iter().position(|x| !x.is_ascii_whitespace()) {
+        return Err(DecodeError::TrailingData { offset: end + x });
+    }
+    return Ok(ret);
+}
+
 #[allow(dead_code)]
 /// Decode bencoded data.
 /// The length of decoded data must be same as the length of input.
+/// This is `decode` discarding the error.
 ///
 /// # Arguments
 /// * `bencode` - bencoded data **without** spaces.
 pub fn decode_len_check(bencode: &[u8]) -> Option<Element> {
-    let mut len = 0;
-    let ret = decode_all(bencode, &mut len);
-    if len != bencode.len() {
-        return None;
-    }
-    return ret;
+    return decode(bencode).ok();
 }
 
 #[allow(dead_code)]
@@ -221,7 +757,103 @@ pub fn decode_len_check(bencode: &[u8]) -> Option<Element> {
 /// * `bencode` - bencoded data **without** spaces.
 pub fn decode_no_len_check(bencode: &[u8]) -> Option<Element> {
     let mut len = 0;
-    decode_all(bencode, &mut len)
+    return decode_all(bencode, 0, &mut len, &DecodeOptions::default(), 0).ok();
+}
+
+/// Decode the first element of `bencode` and return it with the bytes following it.
+/// This allows decoding concatenated elements one at a time.
+///
+/// # Arguments
+/// * `bencode` - bencoded data **without** spaces.
+pub fn decode_prefix(bencode: &[u8]) -> Option<(Element, &[u8])> {
+    let (ret, len) = decode_with_consumed(bencode)?;
+    return Some((ret, &bencode[len..]));
+}
+
+/// Decode the first element of `bencode` and return it with the number of bytes it takes.
+/// Unlike `decode_len_check`, trailing bytes are allowed, so they can be detected and reported.
+///
+/// # Arguments
+/// * `bencode` - bencoded data **without** spaces.
+pub fn decode_with_consumed(bencode: &[u8]) -> Option<(Element, usize)> {
+    let mut len = 0;
+    let ret = decode_all(bencode, 0, &mut len, &DecodeOptions::default(), 0).ok()?;
+    return Some((ret, len));
+}
+
+/// Decode concatenated elements until `bencode` is exhausted.
+/// Returns `None` if any element, including a partial trailing one, fails to decode.
+///
+/// # Arguments
+/// * `bencode` - bencoded data **without** spaces.
+pub fn decode_many(bencode: &[u8]) -> Option<Vec<Element>> {
+    let mut ret = Vec::<Element>::new();
+    let mut rest = bencode;
+    while !rest.is_empty() {
+        let (element, next) = decode_prefix(rest)?;
+        ret.push(element);
+        rest = next;
+    }
+    return Some(ret);
+}
+
+fn encode_bytestring(bytes: &[u8], bencode: &mut Vec<u8>) {
+    bencode.extend_from_slice(bytes.len().to_string().as_bytes());
+    bencode.push(b':');
+    bencode.extend_from_slice(bytes);
+}
+
+fn encode_integer(int: i128, bencode: &mut Vec<u8>) {
+    bencode.push(b'i');
+    bencode.extend_from_slice(int.to_string().as_bytes());
+    bencode.push(b'e');
+}
+
+fn encode_list(list: &[Element], bencode: &mut Vec<u8>) {
+    bencode.push(b'l');
+    for elem in list {
+        encode_all(elem, bencode);
+    }
+    bencode.push(b'e');
+}
+
+fn encode_dictionary(dict: &BTreeMap<Vec<u8>, Element>, bencode: &mut Vec<u8>) {
+    bencode.push(b'd');
+    for (key, val) in dict {
+        encode_bytestring(key, bencode);
+        encode_all(val, bencode);
+    }
+    bencode.push(b'e');
+}
+
+fn encode_all(element: &Element, bencode: &mut Vec<u8>) {
+    match element {
+        Element::ByteString(x) => encode_bytestring(x, bencode),
+        Element::Integer(x) => encode_integer(i128::from(*x), bencode),
+        Element::BigInteger(x) => encode_integer(*x, bencode),
+        Element::List(x) => encode_list(x, bencode),
+        Element::Dictionary(x) => encode_dictionary(x, bencode),
+        Element::Raw(x) => bencode.extend_from_slice(x),
+    }
+}
+
+/// Encode element to bencoded data.
+/// Keys of dictionaries are emitted in sorted raw byte order as BEP 3 requires, which is the iteration order of `BTreeMap`.
+/// `Element::Raw` is copied as it is, which allows embedding an original sub-tree (e.g. `info` of a torrent) unchanged.
+///
+/// # Round-trip
+///
+/// `encode(&decode_len_check(x).unwrap())` is byte-identical to `x` whenever `x` is canonical,
+/// i.e. every dictionary has unique keys in sorted order (integers and lengths never have leading zeros,
+/// otherwise decoding fails, as it does for duplicate keys). Inputs **not** guaranteed to round-trip:
+/// - dictionaries with unsorted keys, which are re-encoded in sorted order.
+///
+/// # Arguments
+/// * `element` - element to encode.
+pub fn encode(element: &Element) -> Vec<u8> {
+    let mut bencode = Vec::<u8>::new();
+    encode_all(element, &mut bencode);
+    return bencode;
 }
 
 #[cfg(test)]
@@ -325,7 +957,7 @@ mod tests {
             helper(
                 "d1:a1:be",
                 Some(Element::Dictionary(
-                    [("a".to_string(), Element::ByteString(vec![b'b']))]
+                    [(b"a".to_vec(), Element::ByteString(vec![b'b']))]
                         .iter()
                         .cloned()
                         .collect(),
@@ -339,13 +971,13 @@ mod tests {
                 "d1:a1:b1:bde1:cli1234e2:abee",
                 Some(Element::Dictionary(
                     [
-                        ("a".to_string(), Element::ByteString(vec![b'b'])),
+                        (b"a".to_vec(), Element::ByteString(vec![b'b'])),
                         (
-                            "b".to_string(),
+                            b"b".to_vec(),
                             Element::Dictionary([].iter().cloned().collect()),
                         ),
                         (
-                            "c".to_string(),
+                            b"c".to_vec(),
                             Element::List(vec![
                                 Element::Integer(1234),
                                 Element::ByteString(vec![b'a', b'b']),
@@ -358,5 +990,1047 @@ mod tests {
                 )),
             );
         }
+
+        #[test]
+        fn decode_len_check_16() {
+            helper("d1:a1:b1:a1:ce", None);
+        }
+    }
+
+    mod decode_test {
+        use super::*;
+
+        fn helper(input: &str, expect: Result<Element, DecodeError>) {
+            let result = decode(input.as_bytes());
+            assert_eq!(result, expect);
+        }
+
+        #[test]
+        fn decode_01() {
+            helper(
+                "li1e1:ae",
+                Ok(Element::List(vec![
+                    Element::Integer(1),
+                    Element::ByteString(vec![b'a']),
+                ])),
+            );
+        }
+
+        #[test]
+        fn decode_02() {
+            helper("", Err(DecodeError::UnexpectedEof));
+        }
+
+        #[test]
+        fn decode_03() {
+            helper("5:abc", Err(DecodeError::UnexpectedEof));
+        }
+
+        #[test]
+        fn decode_04() {
+            helper("li1ei12", Err(DecodeError::UnexpectedEof));
+        }
+
+        #[test]
+        fn decode_05() {
+            helper("li1ei1x2ee", Err(DecodeError::InvalidInteger { offset: 6 }));
+        }
+
+        #[test]
+        fn decode_06() {
+            helper("li-0ee", Err(DecodeError::InvalidInteger { offset: 2 }));
+        }
+
+        #[test]
+        fn decode_07() {
+            helper(
+                "i99999999999999999999e",
+                Ok(Element::BigInteger(99999999999999999999)),
+            );
+            helper(
+                &format!("i{}e", "9".repeat(40)),
+                Err(DecodeError::IntegerOverflow { offset: 1 }),
+            );
+            helper(
+                &format!("l{}:ae", u128::MAX),
+                Err(DecodeError::IntegerOverflow { offset: 1 }),
+            );
+        }
+
+        #[test]
+        fn decode_08() {
+            helper("li03ee", Err(DecodeError::LeadingZero { offset: 2 }));
+        }
+
+        #[test]
+        fn decode_09() {
+            helper("l03:abce", Err(DecodeError::LeadingZero { offset: 1 }));
+        }
+
+        #[test]
+        fn decode_10() {
+            helper("l3abce", Err(DecodeError::ExpectedColon { offset: 2 }));
+        }
+
+        #[test]
+        fn decode_11() {
+            helper("d1:ai1ei2ei3ee", Err(DecodeError::InvalidKey { offset: 7 }));
+        }
+
+        #[test]
+        fn decode_12() {
+            let input = b"d2:\xff\xfei1ee";
+            let result = decode(input);
+            assert_eq!(
+                result,
+                Ok(Element::Dictionary(
+                    [(vec![0xff, 0xfe], Element::Integer(1))]
+                        .iter()
+                        .cloned()
+                        .collect()
+                ))
+            );
+            assert_eq!(encode(&result.unwrap()), input);
+        }
+
+        #[test]
+        fn decode_13() {
+            helper("i1ei2e", Err(DecodeError::TrailingData { offset: 3 }));
+        }
+
+        #[test]
+        fn decode_14() {
+            helper("lxe", Err(DecodeError::InvalidByte { offset: 1 }));
+        }
+
+        #[test]
+        fn decode_15() {
+            helper(
+                "18446744073709551615:a",
+                Err(DecodeError::ByteStringTooLong { offset: 0 }),
+            );
+        }
+
+        #[test]
+        fn decode_16() {
+            helper(
+                "d1:a1:b1:a1:ce",
+                Err(DecodeError::DuplicateKey { offset: 7 }),
+            );
+        }
+
+        #[test]
+        fn decode_17() {
+            helper(
+                "d1:ad1:bi1e1:bi2eee",
+                Err(DecodeError::DuplicateKey { offset: 11 }),
+            );
+        }
+    }
+
+    mod truncated_list_test {
+        use super::*;
+
+        fn helper(input: &str) {
+            assert_eq!(decode(input.as_bytes()), Err(DecodeError::UnexpectedEof));
+            assert_eq!(decode_len_check(input.as_bytes()), None);
+            assert_eq!(decode_no_len_check(input.as_bytes()), None);
+        }
+
+        #[test]
+        fn truncated_list_01() {
+            helper("li1e");
+        }
+
+        #[test]
+        fn truncated_list_02() {
+            helper("l1:a");
+        }
+
+        #[test]
+        fn truncated_list_03() {
+            helper("lli1ee");
+        }
+
+        #[test]
+        fn truncated_list_04() {
+            helper("l");
+        }
+    }
+
+    mod truncated_dictionary_test {
+        use super::*;
+
+        fn helper(input: &str) {
+            assert_eq!(decode(input.as_bytes()), Err(DecodeError::UnexpectedEof));
+            assert_eq!(decode_len_check(input.as_bytes()), None);
+            assert_eq!(decode_no_len_check(input.as_bytes()), None);
+        }
+
+        #[test]
+        fn truncated_dictionary_01() {
+            helper("d1:a1:b");
+        }
+
+        #[test]
+        fn truncated_dictionary_02() {
+            helper("d");
+        }
+
+        #[test]
+        fn truncated_dictionary_03() {
+            helper("d1:a");
+        }
+
+        #[test]
+        fn truncated_dictionary_04() {
+            helper("d1:a1:b1:c");
+        }
+
+        #[test]
+        fn truncated_dictionary_05() {
+            helper("d1:ad1:bi1ee");
+        }
+
+        #[test]
+        fn truncated_dictionary_06() {
+            helper("d1:ali1e");
+        }
+    }
+
+    mod decode_prefix_test {
+        use super::*;
+
+        #[test]
+        fn decode_prefix_01() {
+            let input = b"i1e3:abcli2eee";
+            let (first, rest) = decode_prefix(input).unwrap();
+            assert_eq!(first, Element::Integer(1));
+            assert_eq!(rest, b"3:abcli2eee");
+            let (second, rest) = decode_prefix(rest).unwrap();
+            assert_eq!(second, Element::from("abc"));
+            let (third, rest) = decode_prefix(rest).unwrap();
+            assert_eq!(third, Element::List(vec![Element::Integer(2)]));
+            assert_eq!(rest, b"e");
+        }
+
+        #[test]
+        fn decode_prefix_02() {
+            assert_eq!(
+                decode_prefix(b"de"),
+                Some((Element::Dictionary(BTreeMap::new()), &b""[..]))
+            );
+            assert_eq!(decode_prefix(b""), None);
+            assert_eq!(decode_prefix(b"li1e"), None);
+        }
+    }
+
+    mod decode_with_consumed_test {
+        use super::*;
+
+        #[test]
+        fn decode_with_consumed_01() {
+            assert_eq!(
+                decode_with_consumed(b"d1:ai1eegarbage"),
+                Some((decode(b"d1:ai1ee").unwrap(), 8))
+            );
+            assert_eq!(decode_with_consumed(b"i1e"), Some((Element::Integer(1), 3)));
+        }
+
+        #[test]
+        fn decode_with_consumed_02() {
+            assert_eq!(decode_with_consumed(b""), None);
+            assert_eq!(decode_with_consumed(b"d1:ai1e"), None);
+        }
+    }
+
+    mod decode_many_test {
+        use super::*;
+
+        #[test]
+        fn decode_many_01() {
+            assert_eq!(
+                decode_many(b"i1e3:abcli2eede"),
+                Some(vec![
+                    Element::Integer(1),
+                    Element::from("abc"),
+                    Element::List(vec![Element::Integer(2)]),
+                    Element::Dictionary(BTreeMap::new()),
+                ])
+            );
+            assert_eq!(decode_many(b""), Some(vec![]));
+        }
+
+        #[test]
+        fn decode_many_02() {
+            assert_eq!(decode_many(b"i1e3:ab"), None);
+            assert_eq!(decode_many(b"i1eli2e"), None);
+            assert_eq!(decode_many(b"i1ex"), None);
+        }
+    }
+
+    mod decode_with_limits_test {
+        use super::*;
+
+        fn helper(input: &str, max_depth: usize, expect: Result<Element, DecodeError>) {
+            let limits = DecodeLimits {
+                max_depth,
+                ..Default::default()
+            };
+            let result = decode_with_limits(input.as_bytes(), &limits);
+            assert_eq!(result, expect);
+        }
+
+        #[test]
+        fn decode_with_limits_01() {
+            helper("llee", 2, Ok(Element::List(vec![Element::List(vec![])])));
+        }
+
+        #[test]
+        fn decode_with_limits_02() {
+            helper(
+                "llee",
+                1,
+                Err(DecodeError::DepthLimitExceeded { offset: 1 }),
+            );
+        }
+
+        #[test]
+        fn decode_with_limits_03() {
+            helper(
+                "d1:ad1:bdeee",
+                2,
+                Err(DecodeError::DepthLimitExceeded { offset: 8 }),
+            );
+        }
+
+        #[test]
+        fn decode_with_limits_04() {
+            helper("i1e", 0, Ok(Element::Integer(1)));
+        }
+
+        #[test]
+        fn decode_with_limits_05() {
+            let input = "l".repeat(100000);
+            assert_eq!(
+                decode(input.as_bytes()),
+                Err(DecodeError::DepthLimitExceeded {
+                    offset: DecodeLimits::DEFAULT_MAX_DEPTH
+                })
+            );
+            assert_eq!(decode_no_len_check(input.as_bytes()), None);
+        }
+
+        #[test]
+        fn decode_with_limits_06() {
+            // Deep enough to overflow the call stack of a recursive decoder.
+            let nesting = 1_000_000;
+            let input = "l".repeat(nesting) + &"e".repeat(nesting);
+            let limits = DecodeLimits {
+                max_depth: usize::MAX,
+                ..Default::default()
+            };
+            let mut element = decode_with_limits(input.as_bytes(), &limits).unwrap();
+
+            // Unwrap the lists one by one since dropping them at once would recurse as well.
+            let mut depth = 0;
+            while let Element::List(mut x) = element {
+                depth += 1;
+                element = x.pop().unwrap_or(Element::Integer(0));
+            }
+            assert_eq!(depth, nesting);
+        }
+
+        #[test]
+        fn decode_with_limits_07() {
+            assert_eq!(
+                decode(b"999999999999:abc"),
+                Err(DecodeError::ByteStringTooLong { offset: 0 })
+            );
+            assert_eq!(
+                decode(b"d1:a67108865:abce"),
+                Err(DecodeError::ByteStringTooLong { offset: 4 })
+            );
+        }
+
+        #[test]
+        fn decode_with_limits_08() {
+            let limits = DecodeLimits {
+                max_byte_string_len: 3,
+                ..Default::default()
+            };
+            assert_eq!(
+                decode_with_limits(b"l3:abce", &limits),
+                Ok(Element::List(vec![Element::from("abc")]))
+            );
+            assert_eq!(
+                decode_with_limits(b"l3:abc4:abcde", &limits),
+                Err(DecodeError::ByteStringTooLong { offset: 6 })
+            );
+
+            let limits = DecodeLimits {
+                max_byte_string_len: usize::MAX,
+                ..Default::default()
+            };
+            assert_eq!(
+                decode_with_limits(b"18446744073709551615:a", &limits),
+                Err(DecodeError::UnexpectedEof)
+            );
+        }
+    }
+
+    mod decode_strict_test {
+        use super::*;
+
+        fn helper(input: &str, expect: Result<Element, DecodeError>) {
+            let result = decode_strict(input.as_bytes());
+            assert_eq!(result, expect);
+        }
+
+        #[test]
+        fn decode_strict_01() {
+            helper(
+                "d1:a0:1:b0:e",
+                Ok(Element::Dictionary(
+                    [
+                        (b"a".to_vec(), Element::ByteString(vec![])),
+                        (b"b".to_vec(), Element::ByteString(vec![])),
+                    ]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                )),
+            );
+        }
+
+        #[test]
+        fn decode_strict_02() {
+            helper(
+                "d1:b0:1:a0:e",
+                Err(DecodeError::UnsortedKey {
+                    key: b"a".to_vec(),
+                    offset: 6,
+                }),
+            );
+        }
+
+        #[test]
+        fn decode_strict_03() {
+            helper(
+                "ld1:ad1:c0:2:bb0:eee",
+                Err(DecodeError::UnsortedKey {
+                    key: b"bb".to_vec(),
+                    offset: 11,
+                }),
+            );
+        }
+
+        #[test]
+        fn decode_strict_04() {
+            helper("d1:a0:1:a0:e", Err(DecodeError::DuplicateKey { offset: 6 }));
+        }
+
+        #[test]
+        fn decode_strict_05() {
+            // Raw byte order: "B" (0x42) < "a" (0x61) and "a" < "ab".
+            assert!(decode_strict(b"d1:B0:1:a0:2:ab0:e").is_ok());
+            assert!(decode(b"d1:b0:1:a0:e").is_ok());
+        }
+    }
+
+    mod decode_lenient_test {
+        use super::*;
+
+        fn helper(input: &str, expect: Result<Element, DecodeError>) {
+            let result = decode_lenient(input.as_bytes());
+            assert_eq!(result, expect);
+        }
+
+        #[test]
+        fn decode_lenient_01() {
+            helper("i+5e", Ok(Element::Integer(5)));
+            helper("i007e", Ok(Element::Integer(7)));
+            helper("i-05e", Ok(Element::Integer(-5)));
+            helper("i-0e", Ok(Element::Integer(0)));
+            helper("i00e", Ok(Element::Integer(0)));
+        }
+
+        #[test]
+        fn decode_lenient_02() {
+            helper("03:abc", Ok(Element::from("abc")));
+            helper("00:", Ok(Element::from("")));
+            let element = decode_lenient(b"d1:ai+01e1:b03:abce").unwrap();
+            assert_eq!(encode(&element), b"d1:ai1e1:b3:abce");
+        }
+
+        #[test]
+        fn decode_lenient_03() {
+            helper("i+e", Err(DecodeError::InvalidInteger { offset: 1 }));
+            helper("i+-1e", Err(DecodeError::InvalidInteger { offset: 1 }));
+            helper("i1.5e", Err(DecodeError::InvalidInteger { offset: 2 }));
+            helper("+3:abc", Err(DecodeError::InvalidByte { offset: 0 }));
+            assert_eq!(
+                decode(b"i+5e"),
+                Err(DecodeError::InvalidInteger { offset: 1 })
+            );
+        }
+    }
+
+    mod round_trip_test {
+        use super::*;
+
+        fn helper(input: &str) {
+            let element = decode_len_check(input.as_bytes()).unwrap();
+            assert_eq!(encode(&element), input.as_bytes());
+        }
+
+        #[test]
+        fn round_trip_01() {
+            for input in ["0:", "5:a cde", "i0e", "i-10e", "i1234e"] {
+                helper(input);
+            }
+        }
+
+        #[test]
+        fn round_trip_02() {
+            for input in ["le", "li1ei2ee", "li1e2:ablee"] {
+                helper(input);
+            }
+        }
+
+        #[test]
+        fn round_trip_03() {
+            for input in ["de", "d1:a1:be", "d1:a1:b1:bde1:cli1234e2:abee"] {
+                helper(input);
+            }
+        }
+    }
+
+    mod integer_to_ascii_test {
+        use super::*;
+
+        fn helper(input: Element, expect: Option<&str>) {
+            let result = input.integer_to_ascii();
+            assert_eq!(result, expect.map(|x| x.as_bytes().to_vec()));
+        }
+
+        #[test]
+        fn integer_to_ascii_01() {
+            helper(Element::Integer(0), Some("0"));
+        }
+
+        #[test]
+        fn integer_to_ascii_02() {
+            helper(Element::Integer(-1234), Some("-1234"));
+        }
+
+        #[test]
+        fn integer_to_ascii_03() {
+            helper(Element::Integer(i64::MIN), Some("-9223372036854775808"));
+        }
+
+        #[test]
+        fn integer_to_ascii_04() {
+            helper(Element::ByteString(vec![b'1']), None);
+        }
+    }
+
+    mod get_test {
+        use super::*;
+
+        #[test]
+        fn get_01() {
+            let element = decode(b"d1:ai1e2:\xff\xfei2ee").unwrap();
+            assert_eq!(element.get("a"), Some(&Element::Integer(1)));
+            assert_eq!(element.get("b"), None);
+            assert_eq!(
+                element
+                    .convert_to_ref_dict()
+                    .unwrap()
+                    .get(&vec![0xff, 0xfe]),
+                Some(&Element::Integer(2))
+            );
+        }
+
+        #[test]
+        fn get_02() {
+            assert_eq!(Element::Integer(1).get("a"), None);
+            assert_eq!(Element::List(vec![]).get("a"), None);
+        }
+    }
+
+    mod index_test {
+        use super::*;
+
+        #[test]
+        fn index_01() {
+            let element = decode(b"d4:infod4:name3:abc5:filesli1ei2eeee").unwrap();
+            assert_eq!(
+                element["info"]["name"],
+                Element::ByteString(b"abc".to_vec())
+            );
+            assert_eq!(element["info"]["files"][1], Element::Integer(2));
+        }
+
+        #[test]
+        fn index_02() {
+            let element = decode(b"li1ei2ee").unwrap();
+            assert_eq!(element.get_index(0), Some(&Element::Integer(1)));
+            assert_eq!(element.get_index(2), None);
+            assert_eq!(Element::Integer(1).get_index(0), None);
+        }
+
+        #[test]
+        #[should_panic]
+        fn index_03() {
+            let element = decode(b"d1:ai1ee").unwrap();
+            let _ = &element["b"];
+        }
+
+        #[test]
+        #[should_panic]
+        fn index_04() {
+            let element = decode(b"d1:ai1ee").unwrap();
+            let _ = &element[0];
+        }
+    }
+
+    mod path_test {
+        use super::*;
+
+        #[test]
+        fn path_01() {
+            let element = decode(b"d4:infod4:name3:abc5:filesli1ei2eeee").unwrap();
+            assert_eq!(
+                element.path(&["info", "name"]),
+                Some(&Element::ByteString(b"abc".to_vec()))
+            );
+            assert_eq!(element.path::<&str>(&[]), Some(&element));
+        }
+
+        #[test]
+        fn path_02() {
+            let element = decode(b"d4:infod4:name3:abc5:filesli1ei2eeee").unwrap();
+            assert_eq!(element.path(&["info", "length"]), None);
+            assert_eq!(element.path(&["info", "name", "x"]), None);
+            assert_eq!(element.path(&["missing", "name"]), None);
+        }
+
+        #[test]
+        fn path_03() {
+            let element = decode(b"d4:infod4:name3:abc5:filesli1ei2eeee").unwrap();
+            let path = [
+                PathSegment::Key("info"),
+                PathSegment::Key("files"),
+                PathSegment::Index(1),
+            ];
+            assert_eq!(element.path(&path), Some(&Element::Integer(2)));
+            assert_eq!(element.path(&[PathSegment::Index(0)]), None);
+        }
+    }
+
+    mod from_test {
+        use super::*;
+
+        #[test]
+        fn from_01() {
+            assert_eq!(Element::from(1234), Element::Integer(1234));
+            assert_eq!(Element::from("ab"), Element::ByteString(b"ab".to_vec()));
+            assert_eq!(
+                Element::from("ab".to_string()),
+                Element::ByteString(b"ab".to_vec())
+            );
+            assert_eq!(Element::from(vec![0xffu8]), Element::ByteString(vec![0xff]));
+        }
+
+        #[test]
+        #[cfg(feature = "std")]
+        fn from_02() {
+            let element = Element::from(HashMap::from([
+                ("b".to_string(), Element::from(vec![Element::from(1)])),
+                ("a".to_string(), Element::from("x")),
+            ]));
+            assert_eq!(encode(&element), b"d1:a1:x1:bli1eee");
+        }
+    }
+
+    mod convert_to_integer_test {
+        use super::*;
+
+        #[test]
+        fn convert_to_integer_01() {
+            let element = Element::Integer(-1);
+            assert_eq!(element.convert_to_i64(), Some(-1));
+            assert_eq!(element.convert_to_i32(), Some(-1));
+            assert_eq!(element.convert_to_u64(), None);
+            assert_eq!(element.convert_to_usize(), None);
+            assert_eq!(element.convert_to_u32(), None);
+            assert_eq!(element.convert_to_u16(), None);
+        }
+
+        #[test]
+        fn convert_to_integer_02() {
+            let element = Element::Integer(65536);
+            assert_eq!(element.convert_to_u64(), Some(65536));
+            assert_eq!(element.convert_to_usize(), Some(65536));
+            assert_eq!(element.convert_to_u32(), Some(65536));
+            assert_eq!(element.convert_to_u16(), None);
+            assert_eq!(Element::Integer(65535).convert_to_u16(), Some(65535));
+        }
+
+        #[test]
+        fn convert_to_integer_03() {
+            let element = Element::Integer(i64::from(i32::MAX) + 1);
+            assert_eq!(element.convert_to_i32(), None);
+            assert_eq!(element.convert_to_u32(), Some(1 << 31));
+            assert_eq!(Element::Integer(1 << 32).convert_to_u32(), None);
+            assert_eq!(Element::ByteString(vec![]).convert_to_usize(), None);
+        }
+    }
+
+    mod big_integer_test {
+        use super::*;
+
+        #[test]
+        fn big_integer_01() {
+            let input = b"li-123456789012345678901234567890ei9223372036854775807ee";
+            let element = decode(input).unwrap();
+            assert_eq!(
+                element,
+                Element::List(vec![
+                    Element::BigInteger(-123456789012345678901234567890),
+                    Element::Integer(i64::MAX),
+                ])
+            );
+            assert_eq!(element[0].convert_to_i64(), None);
+            assert_eq!(
+                element[0].convert_to_i128(),
+                Some(-123456789012345678901234567890)
+            );
+            assert_eq!(encode(&element), input);
+        }
+
+        #[test]
+        fn big_integer_02() {
+            assert_eq!(Element::from_i128(1), Element::Integer(1));
+            assert_eq!(
+                Element::from_i128(i128::from(i64::MIN) - 1),
+                Element::BigInteger(i128::from(i64::MIN) - 1)
+            );
+            assert_eq!(
+                decode(format!("i{}e", i128::MIN).as_bytes()),
+                Ok(Element::BigInteger(i128::MIN))
+            );
+            assert_eq!(
+                decode(b"i170141183460469231731687303715884105728e"),
+                Err(DecodeError::IntegerOverflow { offset: 1 })
+            );
+        }
+    }
+
+    mod convert_to_bool_test {
+        use super::*;
+
+        #[test]
+        fn convert_to_bool_01() {
+            assert_eq!(Element::Integer(1).convert_to_bool(), Some(true));
+            assert_eq!(Element::Integer(0).convert_to_bool(), Some(false));
+        }
+
+        #[test]
+        fn convert_to_bool_02() {
+            assert_eq!(Element::Integer(2).convert_to_bool(), None);
+            assert_eq!(Element::Integer(-1).convert_to_bool(), None);
+            assert_eq!(Element::from("1").convert_to_bool(), None);
+        }
+    }
+
+    mod into_test {
+        use super::*;
+
+        #[test]
+        fn into_01() {
+            let element = decode(b"l3:abci1ee").unwrap();
+            let list = element.into_list().unwrap();
+            assert_eq!(list[1], Element::Integer(1));
+            let bytes = list[0].clone().into_byte_string().unwrap();
+            assert_eq!(bytes, b"abc");
+            assert_eq!(list[1].clone().into_byte_string(), None);
+        }
+
+        #[test]
+        fn into_02() {
+            let element = decode(b"d1:ai1ee").unwrap();
+            let dict = element.into_dict().unwrap();
+            assert_eq!(dict[b"a".as_slice()], Element::Integer(1));
+            assert_eq!(Element::Integer(1).into_dict(), None);
+            assert_eq!(Element::Integer(1).into_list(), None);
+        }
+    }
+
+    mod iter_list_test {
+        use super::*;
+
+        #[test]
+        fn iter_list_01() {
+            let element = decode(b"li1ei2ei3ee").unwrap();
+            let sum: i64 = element
+                .iter_list()
+                .unwrap()
+                .map(|x| x.convert_to_i64().unwrap())
+                .sum();
+            assert_eq!(sum, 6);
+            assert!(Element::Integer(1).iter_list().is_none());
+        }
+
+        #[test]
+        fn iter_list_02() {
+            let mut element = decode(b"li1ei2ee").unwrap();
+            for x in element.iter_list_mut().unwrap() {
+                *x = Element::from(x.convert_to_i64().unwrap() * 10);
+            }
+            assert_eq!(encode(&element), b"li10ei20ee");
+            assert!(Element::Integer(1).iter_list_mut().is_none());
+        }
+    }
+
+    mod as_host_port_list_test {
+        use super::*;
+
+        fn helper(input: &str, expect: Option<Vec<(&str, u16)>>) {
+            let result = decode_len_check(input.as_bytes())
+                .unwrap()
+                .as_host_port_list();
+            let expect = expect.map(|x| {
+                x.iter()
+                    .map(|(host, port)| (host.to_string(), *port))
+                    .collect()
+            });
+            assert_eq!(result, expect);
+        }
+
+        #[test]
+        fn as_host_port_list_01() {
+            helper(
+                "ll9:127.0.0.1i6881eel11:example.comi0eee",
+                Some(vec![("127.0.0.1", 6881), ("example.com", 0)]),
+            );
+        }
+
+        #[test]
+        fn as_host_port_list_02() {
+            helper("ll1:a2:\x1a\x1bee", Some(vec![("a", 0x1a1b)]));
+        }
+
+        #[test]
+        fn as_host_port_list_03() {
+            helper("ll9:127.0.0.1ee", None);
+        }
+
+        #[test]
+        fn as_host_port_list_04() {
+            helper("ll9:127.0.0.1i6881ei1eee", None);
+        }
+
+        #[test]
+        fn as_host_port_list_05() {
+            helper("ll9:127.0.0.1i65536eee", None);
+        }
+
+        #[test]
+        fn as_host_port_list_06() {
+            helper("ll9:127.0.0.1i-1eee", None);
+        }
+    }
+
+    mod encode_test {
+        use super::*;
+
+        fn helper(input: &Element, expect: &str) {
+            let result = encode(input);
+            assert_eq!(result, expect.as_bytes());
+        }
+
+        #[test]
+        fn encode_01() {
+            helper(&Element::ByteString(Vec::<u8>::new()), "0:");
+        }
+
+        #[test]
+        fn encode_02() {
+            helper(&Element::Integer(-10), "i-10e");
+        }
+
+        #[test]
+        fn encode_03() {
+            helper(
+                &Element::List(vec![
+                    Element::Integer(1),
+                    Element::ByteString(vec![b'a', b'b']),
+                    Element::List(Vec::<Element>::new()),
+                ]),
+                "li1e2:ablee",
+            );
+        }
+
+        #[test]
+        fn encode_04() {
+            helper(
+                &Element::Dictionary(
+                    [
+                        (b"c".to_vec(), Element::Integer(3)),
+                        (b"a".to_vec(), Element::Integer(1)),
+                        (b"b".to_vec(), Element::Integer(2)),
+                    ]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                ),
+                "d1:ai1e1:bi2e1:ci3ee",
+            );
+        }
+
+        #[test]
+        fn encode_05() {
+            helper(&Element::Raw(b"d1:b0:1:a0:e".to_vec()), "d1:b0:1:a0:e");
+        }
+
+        #[test]
+        fn encode_06() {
+            // `info` is not canonical (unsorted keys), so only a raw passthrough keeps its bytes and the info-hash.
+            let info = "d4:name1:x1:ai1ee";
+            let torrent = format!("d7:comment3:old4:info{}e", info);
+            let mut dict = decode_len_check(torrent.as_bytes())
+                .unwrap()
+                .convert_to_dict()
+                .unwrap();
+            dict.insert(b"comment".to_vec(), Element::ByteString(b"new".to_vec()));
+            dict.insert(b"info".to_vec(), Element::Raw(info.as_bytes().to_vec()));
+
+            let edited = format!("d7:comment3:new4:info{}e", info);
+            helper(&Element::Dictionary(dict), edited.as_str());
+        }
+
+        #[test]
+        fn encode_07() {
+            // Raw byte order puts upper case before lower case and a prefix before longer keys.
+            helper(
+                &Element::Dictionary(
+                    [
+                        (b"ab".to_vec(), Element::Integer(3)),
+                        (b"a".to_vec(), Element::Integer(2)),
+                        (b"B".to_vec(), Element::Integer(1)),
+                    ]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                ),
+                "d1:Bi1e1:ai2e2:abi3ee",
+            );
+        }
+
+        #[test]
+        fn encode_08() {
+            // Decoding a canonical buffer and encoding it again gives the same bytes.
+            let bencode =
+                "d8:announce9:localhost4:infod6:lengthi12e4:name5:a.txt12:piece lengthi16384eee";
+            helper(&decode_len_check(bencode.as_bytes()).unwrap(), bencode);
+        }
+
+        #[test]
+        fn encode_09() {
+            // An unsorted dictionary decodes fine but is encoded in sorted order.
+            helper(&decode_len_check(b"d1:b0:1:a0:e").unwrap(), "d1:a0:1:b0:e");
+        }
+    }
+
+    mod len_test {
+        use super::*;
+
+        #[test]
+        fn len_01() {
+            let element = decode(b"d1:a3:abc1:bli1ei2ee1:cdee").unwrap();
+            assert_eq!(element.len(), Some(3));
+            assert_eq!(element["a"].len(), Some(3));
+            assert_eq!(element["b"].len(), Some(2));
+            assert_eq!(element["c"].len(), Some(0));
+            assert_eq!(element["c"].is_empty(), Some(true));
+            assert_eq!(element["b"].is_empty(), Some(false));
+        }
+
+        #[test]
+        fn len_02() {
+            assert_eq!(Element::Integer(0).len(), None);
+            assert_eq!(Element::from_i128(i128::MAX).is_empty(), None);
+            assert_eq!(Element::Raw(b"le".to_vec()).len(), None);
+            assert_eq!(Element::ByteString(Vec::new()).is_empty(), Some(true));
+        }
+    }
+
+    mod as_bytes_test {
+        use super::*;
+
+        #[test]
+        fn as_bytes_01() {
+            let element = decode(b"l3:a\xffbi1ee").unwrap();
+            assert_eq!(element[0].as_bytes(), Some(b"a\xffb".as_slice()));
+            assert_eq!(element[1].as_bytes(), None);
+            assert_eq!(element.as_bytes(), None);
+        }
+    }
+
+    mod convert_to_bytes_list_test {
+        use super::*;
+
+        #[test]
+        fn convert_to_bytes_list_01() {
+            let element = decode(b"l3:a\xffb0:1:ce").unwrap();
+            assert_eq!(element.convert_to_string_list(), None);
+            assert_eq!(
+                element.convert_to_bytes_list(),
+                Some(vec![b"a\xffb".to_vec(), Vec::new(), b"c".to_vec()])
+            );
+        }
+
+        #[test]
+        fn convert_to_bytes_list_02() {
+            assert_eq!(
+                decode(b"le").unwrap().convert_to_bytes_list(),
+                Some(Vec::new())
+            );
+            for bencode in [b"l1:ai1ee".as_slice(), b"1:a", b"llee", b"d1:a1:be"] {
+                assert_eq!(decode(bencode).unwrap().convert_to_bytes_list(), None);
+            }
+        }
+    }
+
+    mod decode_trim_test {
+        use super::*;
+
+        #[test]
+        fn decode_trim_01() {
+            let expected = decode(b"d1:a3:x ye").unwrap();
+            assert_eq!(decode_trim(b"d1:a3:x ye"), Ok(expected.clone()));
+            assert_eq!(decode_trim(b" \t\r\nd1:a3:x ye\n"), Ok(expected));
+            // Whitespace at the edges of a byte string is part of it.
+            assert_eq!(
+                decode_trim(b"  3: a \n"),
+                Ok(Element::ByteString(b" a ".to_vec()))
+            );
+        }
+
+        #[test]
+        fn decode_trim_02() {
+            // Whitespace between tokens is still an error, at its offset in the input.
+            assert_eq!(
+                decode_trim(b"  li1e i2ee"),
+                Err(DecodeError::InvalidByte { offset: 6 })
+            );
+            assert_eq!(
+                decode_trim(b"\ni1ei2e"),
+                Err(DecodeError::TrailingData { offset: 4 })
+            );
+            assert_eq!(decode_trim(b" \n "), Err(DecodeError::UnexpectedEof));
+        }
     }
 }
@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+use crate::{decode_bytestring_header, decode_integer, DecodeError, DecodeLimits, Element};
+
+/// Longest `<length>:` or `i<integer>e` token which can be valid (`i-170141183460469231731687303715884105728e`).
+const MAX_TOKEN_LEN: usize = 42;
+
+struct Decoder<'a, R: Read> {
+    reader: &'a mut R,
+    offset: usize,
+    limits: DecodeLimits,
+}
+
+impl<R: Read> Decoder<'_, R> {
+    fn read_byte(&mut self) -> Result<u8, DecodeError> {
+        let mut byte = [0u8; 1];
+        match self.reader.read_exact(&mut byte) {
+            Ok(()) => {
+                self.offset += 1;
+                return Ok(byte[0]);
+            }
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(DecodeError::UnexpectedEof);
+            }
+            Err(e) => {
+                return Err(DecodeError::Io(e.kind()));
+            }
+        }
+    }
+
+    /// Read a token starting with `first` up to (and including) the first byte which is not part of a number.
+    fn read_token(&mut self, first: u8) -> Result<Vec<u8>, DecodeError> {
+        let mut token = vec![first];
+        loop {
+            let byte = self.read_byte()?;
+            token.push(byte);
+            if !(byte.is_ascii_digit() || byte == b'-') || token.len() > MAX_TOKEN_LEN {
+                return Ok(token);
+            }
+        }
+    }
+
+    fn decode_bytesstring(&mut self, first: u8) -> Result<Vec<u8>, DecodeError> {
+        let offset = self.offset - 1;
+        let token = self.read_token(first)?;
+        let mut header_len = 0;
+        let bytes_len = decode_bytestring_header(&token, offset, &mut header_len, false)?;
+        if bytes_len > self.limits.max_byte_string_len {
+            return Err(DecodeError::ByteStringTooLong { offset });
+        }
+
+        let mut bytes = Vec::<u8>::new();
+        let read_len = match self.reader.take(bytes_len as u64).read_to_end(&mut bytes) {
+            Ok(x) => x,
+            Err(e) => return Err(DecodeError::Io(e.kind())),
+        };
+        self.offset += read_len;
+        if read_len != bytes_len {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        return Ok(bytes);
+    }
+
+    fn decode_integer(&mut self) -> Result<Element, DecodeError> {
+        let offset = self.offset - 1;
+        let token = self.read_token(b'i')?;
+        if token.len() > MAX_TOKEN_LEN {
+            return Err(DecodeError::InvalidInteger { offset: offset + 1 });
+        }
+        let mut len = 0;
+        return decode_integer(&token, offset, &mut len, false).map(Element::from_i128);
+    }
+
+    fn decode_list(&mut self, depth: usize) -> Result<Element, DecodeError> {
+        if depth >= self.limits.max_depth {
+            return Err(DecodeError::DepthLimitExceeded {
+                offset: self.offset - 1,
+            });
+        }
+
+        let mut list = Vec::<Element>::new();
+        loop {
+            let byte = self.read_byte()?;
+            if byte == b'e' {
+                return Ok(Element::List(list));
+            }
+            list.push(self.decode_all(byte, depth + 1)?);
+        }
+    }
+
+    fn decode_dictionary(&mut self, depth: usize) -> Result<Element, DecodeError> {
+        if depth >= self.limits.max_depth {
+            return Err(DecodeError::DepthLimitExceeded {
+                offset: self.offset - 1,
+            });
+        }
+
+        let mut dict = BTreeMap::<Vec<u8>, Element>::new();
+        loop {
+            let byte = self.read_byte()?;
+            if byte == b'e' {
+                return Ok(Element::Dictionary(dict));
+            }
+
+            let key_offset = self.offset - 1;
+            if !byte.is_ascii_digit() {
+                return Err(DecodeError::InvalidKey { offset: key_offset });
+            }
+            let dict_key = self.decode_bytesstring(byte)?;
+
+            let byte = self.read_byte()?;
+            let dict_val = self.decode_all(byte, depth + 1)?;
+            if dict.insert(dict_key, dict_val).is_some() {
+                return Err(DecodeError::DuplicateKey { offset: key_offset });
+            }
+        }
+    }
+
+    fn decode_all(&mut self, first: u8, depth: usize) -> Result<Element, DecodeError> {
+        match first {
+            b'0'..=b'9' => {
+                return Ok(Element::ByteString(self.decode_bytesstring(first)?));
+            }
+            b'i' => {
+                return self.decode_integer();
+            }
+            b'l' => {
+                return self.decode_list(depth);
+            }
+            b'd' => {
+                return self.decode_dictionary(depth);
+            }
+            _ => {
+                return Err(DecodeError::InvalidByte {
+                    offset: self.offset - 1,
+                });
+            }
+        }
+    }
+}
+
+/// Decode one bencoded element from `reader`, pulling bytes only as needed.
+/// Reading stops right after the end of the element, so the rest of the stream is left unread.
+/// `reader` is read byte by byte, so wrap unbuffered sources (e.g. `TcpStream`) with `BufReader`.
+///
+/// # Arguments
+/// * `reader` - source of bencoded data **without** spaces.
+pub fn decode_from_reader<R: Read>(reader: &mut R) -> Result<Element, DecodeError> {
+    return decode_from_reader_with_limits(reader, &DecodeLimits::default());
+}
+
+/// Same as `decode_from_reader`, but with the given `limits` instead of the default ones.
+///
+/// # Arguments
+/// * `reader` - source of bencoded data **without** spaces.
+/// * `limits` - limits which make decoding fail with an error when exceeded.
+pub fn decode_from_reader_with_limits<R: Read>(
+    reader: &mut R,
+    limits: &DecodeLimits,
+) -> Result<Element, DecodeError> {
+    let mut decoder = Decoder {
+        reader,
+        offset: 0,
+        limits: *limits,
+    };
+    let first = decoder.read_byte()?;
+    return decoder.decode_all(first, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod decode_from_reader_test {
+        use super::*;
+
+        fn helper(input: &str, expect: Result<Element, DecodeError>, expect_rest: &str) {
+            let mut reader = input.as_bytes();
+            let result = decode_from_reader(&mut reader);
+            assert_eq!(result, expect);
+            assert_eq!(reader, expect_rest.as_bytes());
+        }
+
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))
+            }
+        }
+
+        #[test]
+        fn decode_from_reader_01() {
+            helper("i-10e", Ok(Element::Integer(-10)), "");
+        }
+
+        #[test]
+        fn decode_from_reader_02() {
+            helper(
+                "d1:a1:b1:bde1:cli1234e2:abee",
+                Ok(Element::Dictionary(
+                    [
+                        (b"a".to_vec(), Element::ByteString(vec![b'b'])),
+                        (
+                            b"b".to_vec(),
+                            Element::Dictionary([].iter().cloned().collect()),
+                        ),
+                        (
+                            b"c".to_vec(),
+                            Element::List(vec![
+                                Element::Integer(1234),
+                                Element::ByteString(vec![b'a', b'b']),
+                            ]),
+                        ),
+                    ]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                )),
+                "",
+            );
+        }
+
+        #[test]
+        fn decode_from_reader_03() {
+            helper(
+                "l0:4:spame4:rest",
+                Ok(Element::List(vec![
+                    Element::ByteString(vec![]),
+                    Element::ByteString(b"spam".to_vec()),
+                ])),
+                "4:rest",
+            );
+        }
+
+        #[test]
+        fn decode_from_reader_04() {
+            let mut reader: &[u8] = b"i1e3:abc";
+            assert_eq!(decode_from_reader(&mut reader), Ok(Element::Integer(1)));
+            assert_eq!(
+                decode_from_reader(&mut reader),
+                Ok(Element::ByteString(b"abc".to_vec()))
+            );
+            assert_eq!(
+                decode_from_reader(&mut reader),
+                Err(DecodeError::UnexpectedEof)
+            );
+        }
+
+        #[test]
+        fn decode_from_reader_05() {
+            helper("li1e", Err(DecodeError::UnexpectedEof), "");
+        }
+
+        #[test]
+        fn decode_from_reader_06() {
+            helper("5:abc", Err(DecodeError::UnexpectedEof), "");
+        }
+
+        #[test]
+        fn decode_from_reader_07() {
+            helper("li03ee", Err(DecodeError::LeadingZero { offset: 2 }), "e");
+        }
+
+        #[test]
+        fn decode_from_reader_08() {
+            helper(
+                "d1:ai1ei2ei3ee",
+                Err(DecodeError::InvalidKey { offset: 7 }),
+                "2ei3ee",
+            );
+        }
+
+        #[test]
+        fn decode_from_reader_09() {
+            helper(
+                "l3abce",
+                Err(DecodeError::ExpectedColon { offset: 2 }),
+                "bce",
+            );
+        }
+
+        #[test]
+        fn decode_from_reader_10() {
+            let input = "l".repeat(1000);
+            let mut reader = input.as_bytes();
+            assert_eq!(
+                decode_from_reader(&mut reader),
+                Err(DecodeError::DepthLimitExceeded {
+                    offset: DecodeLimits::DEFAULT_MAX_DEPTH
+                })
+            );
+        }
+
+        #[test]
+        fn decode_from_reader_11() {
+            let input = format!("i{}e", "9".repeat(50));
+            helper(
+                &input,
+                Err(DecodeError::InvalidInteger { offset: 1 }),
+                &("9".repeat(8) + "e"),
+            );
+        }
+
+        #[test]
+        fn decode_from_reader_12() {
+            helper(
+                "d1:a1:b1:a1:ce",
+                Err(DecodeError::DuplicateKey { offset: 7 }),
+                "e",
+            );
+        }
+
+        #[test]
+        fn decode_from_reader_13() {
+            assert_eq!(
+                decode_from_reader(&mut FailingReader),
+                Err(DecodeError::Io(io::ErrorKind::ConnectionReset))
+            );
+        }
+
+        #[test]
+        fn decode_from_reader_14() {
+            let limits = DecodeLimits {
+                max_byte_string_len: 3,
+                ..Default::default()
+            };
+            let mut reader: &[u8] = b"l3:abc4:abcde";
+            assert_eq!(
+                decode_from_reader_with_limits(&mut reader, &limits),
+                Err(DecodeError::ByteStringTooLong { offset: 6 })
+            );
+            assert_eq!(reader, b"abcde");
+
+            let mut reader: &[u8] = b"999999999999:abc";
+            assert_eq!(
+                decode_from_reader(&mut reader),
+                Err(DecodeError::ByteStringTooLong { offset: 0 })
+            );
+        }
+
+        #[test]
+        fn decode_from_reader_15() {
+            let input = format!("i{}e", i128::MIN);
+            helper(&input, Ok(Element::BigInteger(i128::MIN)), "");
+        }
+    }
+}
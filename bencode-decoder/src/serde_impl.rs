@@ -0,0 +1,167 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::{decode, Element};
+
+/// Prefix of byte strings which are serialized as base64.
+/// UTF-8 byte strings starting with it are base64-encoded too, so the mapping stays lossless.
+pub const BASE64_PREFIX: &str = "base64:";
+
+fn bytes_to_string(bytes: &[u8]) -> String {
+    match core::str::from_utf8(bytes) {
+        Ok(x) if !x.starts_with(BASE64_PREFIX) => return x.to_string(),
+        _ => return format!("{}{}", BASE64_PREFIX, STANDARD.encode(bytes)),
+    }
+}
+
+fn string_to_bytes<E: de::Error>(s: &str) -> Result<Vec<u8>, E> {
+    match s.strip_prefix(BASE64_PREFIX) {
+        Some(x) => return STANDARD.decode(x).map_err(E::custom),
+        None => return Ok(s.as_bytes().to_vec()),
+    }
+}
+
+/// Byte strings map to strings (base64 with [`BASE64_PREFIX`] when not UTF-8), integers to numbers,
+/// lists to arrays and dictionaries to maps. `Raw` is serialized as the element it decodes to.
+impl Serialize for Element {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Element::ByteString(x) => return serializer.serialize_str(&bytes_to_string(x)),
+            Element::Integer(x) => return serializer.serialize_i64(*x),
+            Element::BigInteger(x) => return serializer.serialize_i128(*x),
+            Element::List(x) => {
+                let mut seq = serializer.serialize_seq(Some(x.len()))?;
+                for val in x {
+                    seq.serialize_element(val)?;
+                }
+                return seq.end();
+            }
+            Element::Dictionary(x) => {
+                let mut map = serializer.serialize_map(Some(x.len()))?;
+                for (key, val) in x {
+                    map.serialize_entry(&bytes_to_string(key), val)?;
+                }
+                return map.end();
+            }
+            Element::Raw(x) => {
+                let element = decode(x).map_err(ser::Error::custom)?;
+                return element.serialize(serializer);
+            }
+        }
+    }
+}
+
+struct ElementVisitor;
+
+impl<'de> Visitor<'de> for ElementVisitor {
+    type Value = Element;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string, an integer, an array or a map")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Element, E> {
+        return Ok(Element::Integer(v));
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Element, E> {
+        return Ok(Element::from_i128(i128::from(v)));
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Element, E> {
+        return Ok(Element::from_i128(v));
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Element, E> {
+        match i128::try_from(v) {
+            Ok(x) => return Ok(Element::from_i128(x)),
+            Err(_) => return Err(E::custom(format!("integer {} is out of range", v))),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Element, E> {
+        return Ok(Element::ByteString(string_to_bytes(v)?));
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Element, E> {
+        return Ok(Element::ByteString(v.to_vec()));
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Element, A::Error> {
+        let mut list = Vec::<Element>::new();
+        while let Some(val) = seq.next_element()? {
+            list.push(val);
+        }
+        return Ok(Element::List(list));
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Element, A::Error> {
+        let mut dict = BTreeMap::<Vec<u8>, Element>::new();
+        while let Some((key, val)) = map.next_entry::<String, Element>()? {
+            dict.insert(string_to_bytes(&key)?, val);
+        }
+        return Ok(Element::Dictionary(dict));
+    }
+}
+
+impl<'de> Deserialize<'de> for Element {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Element, D::Error> {
+        return deserializer.deserialize_any(ElementVisitor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod serde_test {
+        use super::*;
+
+        fn sample_torrent() -> Vec<u8> {
+            let mut ret = b"d8:announce20:http://example.com/a7:comment13:base64:looks!4:infod6:lengthi40000e4:name5:a.bin12:piece lengthi16384e6:pieces20:".to_vec();
+            ret.extend_from_slice(&[0xffu8; 20]);
+            ret.extend_from_slice(b"ee");
+            return ret;
+        }
+
+        #[test]
+        fn serde_01() {
+            let element = decode(&sample_torrent()).unwrap();
+            let json = serde_json::to_value(&element).unwrap();
+            assert_eq!(json["announce"], "http://example.com/a");
+            assert_eq!(json["info"]["length"], 40000);
+            assert_eq!(
+                json["info"]["pieces"],
+                "base64:".to_string() + &STANDARD.encode([0xffu8; 20])
+            );
+            assert_eq!(
+                json["comment"],
+                "base64:".to_string() + &STANDARD.encode(b"base64:looks!")
+            );
+        }
+
+        #[test]
+        fn serde_02() {
+            let element = decode(&sample_torrent()).unwrap();
+            let json = serde_json::to_string(&element).unwrap();
+            let round_trip: Element = serde_json::from_str(&json).unwrap();
+            assert_eq!(round_trip, element);
+        }
+
+        #[test]
+        fn serde_03() {
+            let raw = Element::List(vec![Element::Raw(b"d1:ai1ee".to_vec())]);
+            assert_eq!(serde_json::to_string(&raw).unwrap(), r#"[{"a":1}]"#);
+            assert!(serde_json::from_str::<Element>("1.5").is_err());
+            assert!(serde_json::from_str::<Element>("null").is_err());
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{decode, decode_bytestring_header, decode_integer, DecodeError, Element};
+
+/// Byte range `start..end` of an element within the decoded buffer.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn range(&self) -> Range<usize> {
+        return self.start..self.end;
+    }
+}
+
+/// Spans of a decoded element and of everything nested in it, in the same shape as `Element`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SpanMap {
+    /// Span of a byte string or an integer.
+    Leaf(Span),
+    List(Span, Vec<SpanMap>),
+    Dictionary(Span, BTreeMap<Vec<u8>, SpanMap>),
+}
+
+impl SpanMap {
+    pub fn span(&self) -> Span {
+        match self {
+            SpanMap::Leaf(x) | SpanMap::List(x, _) | SpanMap::Dictionary(x, _) => return *x,
+        }
+    }
+
+    /// Spans of the value of `key` if `self` is a dictionary.
+    pub fn get(&self, key: &str) -> Option<&SpanMap> {
+        if let SpanMap::Dictionary(_, x) = self {
+            x.get(key.as_bytes())
+        } else {
+            None
+        }
+    }
+
+    /// Spans of the `index`-th element if `self` is a list.
+    pub fn get_index(&self, index: usize) -> Option<&SpanMap> {
+        if let SpanMap::List(_, x) = self {
+            x.get(index)
+        } else {
+            None
+        }
+    }
+}
+
+/// Return the spans of the element starting at `offset`, which must already be known to be valid.
+fn scan_all(bencode: &[u8], offset: usize) -> SpanMap {
+    match bencode[offset] {
+        b'0'..=b'9' => {
+            let mut header_len = 0;
+            let bytes_len =
+                decode_bytestring_header(&bencode[offset..], offset, &mut header_len, false)
+                    .unwrap();
+            return SpanMap::Leaf(Span {
+                start: offset,
+                end: offset + header_len + bytes_len,
+            });
+        }
+        b'i' => {
+            let mut len = 0;
+            decode_integer(&bencode[offset..], offset, &mut len, false).unwrap();
+            return SpanMap::Leaf(Span {
+                start: offset,
+                end: offset + len,
+            });
+        }
+        b'l' => {
+            let mut list = Vec::<SpanMap>::new();
+            let mut idx = offset + 1;
+            while bencode[idx] != b'e' {
+                let val = scan_all(bencode, idx);
+                idx = val.span().end;
+                list.push(val);
+            }
+            let span = Span {
+                start: offset,
+                end: idx + 1,
+            };
+            return SpanMap::List(span, list);
+        }
+        _ => {
+            let mut dict = BTreeMap::<Vec<u8>, SpanMap>::new();
+            let mut idx = offset + 1;
+            while bencode[idx] != b'e' {
+                let key = scan_all(bencode, idx).span();
+                let mut header_len = 0;
+                decode_bytestring_header(&bencode[idx..], idx, &mut header_len, false).unwrap();
+                let val = scan_all(bencode, key.end);
+                idx = val.span().end;
+                dict.insert(bencode[(key.start + header_len)..key.end].to_vec(), val);
+            }
+            let span = Span {
+                start: offset,
+                end: idx + 1,
+            };
+            return SpanMap::Dictionary(span, dict);
+        }
+    }
+}
+
+/// Same as `decode`, but also return where every element appeared in `bencode`.
+/// This makes it possible to hash the exact bytes of a sub-element, e.g. the `info` dictionary:
+/// `&bencode[spans.get("info").unwrap().span().range()]`.
+///
+/// # Arguments
+/// * `bencode` - bencoded data **without** spaces.
+pub fn decode_with_spans(bencode: &[u8]) -> Result<(Element, SpanMap), DecodeError> {
+    let element = decode(bencode)?;
+    let spans = scan_all(bencode, 0);
+    return Ok((element, spans));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod decode_with_spans_test {
+        use super::*;
+
+        #[test]
+        fn decode_with_spans_01() {
+            let input = b"d8:announce3:abc4:infod6:lengthi3e4:name1:aee";
+            let (element, spans) = decode_with_spans(input).unwrap();
+            assert_eq!(spans.span(), Span { start: 0, end: 45 });
+
+            let info = spans.get("info").unwrap().span();
+            assert_eq!(&input[info.range()], b"d6:lengthi3e4:name1:ae");
+            assert_eq!(&input[info.range()], crate::encode(&element["info"]));
+            assert_eq!(
+                spans.get("info").unwrap().get("length").unwrap().span(),
+                Span { start: 31, end: 34 }
+            );
+        }
+
+        #[test]
+        fn decode_with_spans_02() {
+            // Unsorted keys are sorted in `Element`, but spans still point at the original bytes.
+            let input = b"l0:d1:bi1e1:ai2eee";
+            let (_, spans) = decode_with_spans(input).unwrap();
+            assert_eq!(
+                spans.get_index(0).unwrap().span(),
+                Span { start: 1, end: 3 }
+            );
+            let dict = spans.get_index(1).unwrap();
+            assert_eq!(dict.span(), Span { start: 3, end: 17 });
+            assert_eq!(dict.get("a").unwrap().span(), Span { start: 13, end: 16 });
+            assert_eq!(dict.get("b").unwrap().span(), Span { start: 7, end: 10 });
+            assert_eq!(spans.get_index(2), None);
+            assert_eq!(spans.get("a"), None);
+        }
+
+        #[test]
+        fn decode_with_spans_03() {
+            assert_eq!(decode_with_spans(b"li1e"), Err(DecodeError::UnexpectedEof));
+        }
+    }
+}
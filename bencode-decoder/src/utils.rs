@@ -1,77 +1,109 @@
-/// Decode slice to u64.
+/// Why `decode_u128_checked` or `decode_i128_checked` failed.
+/// On success, the number ended at a non-digit if `len` is less than the input length,
+/// and at the end of the input otherwise.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NumberError {
+    /// No digit at the beginning, including empty input and a lone sign.
+    NoDigits,
+    /// The digits do not fit in the type. `len` is past all of them.
+    Overflow,
+    /// `-0`, which is only allowed in lenient mode.
+    NegativeZero,
+}
+
+/// Decode slice to u128.
 /// This function does not allow numbers starting with '+'.
+/// Unless `lenient` is true, a number starting with `0` is `0` itself: decoding stops right after
+/// the first `0`, so `"007"` returns `Some(0)` and `len` is 1, leaving the caller to reject the
+/// following digit. If `lenient` is true, leading zeros are allowed (e.g. `"007"` returns `Some(7)`
+/// and `len` is 3).
 ///
 /// # Arguments
 ///
 /// * `ascii_num` - slice to decode
 /// * `len` - the length of decoded ascii number (if decoding fails it means max length of correct ascii numbers)
+/// * `lenient` - whether to allow leading zeros
 ///
 /// # Example
 ///
 /// For convenience, below examples use `ascii_num` as `&str`.
 ///
-/// - `decode_u64("1234", &mut len)` returns `Some(1234)` and `len` must be 4.
-/// - `decode_u64("1234+1234", &mut len)` returns `Some(1234)` and len must be 4.
-/// - `decode_u64("1234abcd", &mut len)` returns `Some(1234)` and len must be 4.
-/// - `decode_u64("-1234", &mut len)` returns `None` and `len` must be 0.
-/// - `decode_u64("+1234", &mut len)` returns `None` and `len` must be 0.
-/// - `decode_u64("18446744073709551615", &mut len)` returns `Some(18446744073709551615)` and `len` must be 20. Note that `18446744073709551615` is `u64::MAX`.
-/// - `decode_u64("18446744073709551616", &mut len)` returns `None` and `len` must be 20. Note that `18446744073709551616` is `u64::MAX + 1`.
-pub fn decode_u64(ascii_num: &[u8], len: &mut usize) -> Option<u64> {
-    if ascii_num.len() == 0 {
-        *len = 0;
-        return None;
-    } else if ascii_num.len() >= 2 && ascii_num[0] == b'0' {
-        *len = 1;
-        return Some(0);
-    }
-
-    let mut num: u64 = 0;
+/// - `decode_u128_checked("1234", &mut len, false)` returns `Ok(1234)` and `len` must be 4.
+/// - `decode_u128_checked("1234+1234", &mut len, false)` returns `Ok(1234)` and len must be 4.
+/// - `decode_u128_checked("1234abcd", &mut len, false)` returns `Ok(1234)` and len must be 4.
+/// - `decode_u128_checked("-1234", &mut len, false)` returns `Err(NumberError::NoDigits)` and `len` must be 0.
+/// - `decode_u128_checked("+1234", &mut len, false)` returns `Err(NumberError::NoDigits)` and `len` must be 0.
+/// - `decode_u128_checked("340282366920938463463374607431768211455", &mut len, false)` returns `Ok(340282366920938463463374607431768211455)` and `len` must be 39. Note that `340282366920938463463374607431768211455` is `u128::MAX`.
+/// - `decode_u128_checked("340282366920938463463374607431768211456", &mut len, false)` returns `Err(NumberError::Overflow)` and `len` must be 39. Note that `340282366920938463463374607431768211456` is `u128::MAX + 1`.
+/// - `decode_u128_checked("0", &mut len, false)`, `decode_u128_checked("00", &mut len, false)` and `decode_u128_checked("0x", &mut len, false)` return `Ok(0)` and `len` must be 1.
+/// - `decode_u128_checked("", &mut len, false)` returns `Err(NumberError::NoDigits)` and `len` must be 0.
+pub fn decode_u128_checked(
+    ascii_num: &[u8],
+    len: &mut usize,
+    lenient: bool,
+) -> Result<u128, NumberError> {
+    *len = 0;
+    let mut num = Some(0u128);
     for cur in ascii_num {
-        match *cur {
-            b'0'..=b'9' => {
-                *len += 1;
-
-                num = num.checked_mul(10)?;
-                num = num.checked_add((cur - b'0') as u64)?;
-            }
-            _ => {
-                break;
-            }
+        let is_leading_zero = !lenient && *len == 1 && num == Some(0);
+        if !cur.is_ascii_digit() || is_leading_zero {
+            break;
         }
+        *len += 1;
+
+        num = num
+            .and_then(|x| x.checked_mul(10))
+            .and_then(|x| x.checked_add((cur - b'0') as u128));
     }
 
-    if num == 0 && (*len == 0 || *len > 1) {
-        return None;
+    if *len == 0 {
+        return Err(NumberError::NoDigits);
     }
 
-    return Some(num);
+    return num.ok_or(NumberError::Overflow);
 }
 
-/// Decode slice to i64.
-/// This function does not allow number starting with '+'.
+/// `decode_u128_checked` for u64 in strict mode, which tests use to pin the u64 boundary.
+#[cfg(test)]
+pub fn decode_u64(ascii_num: &[u8], len: &mut usize) -> Option<u64> {
+    return decode_u64_checked(ascii_num, len).ok();
+}
+
+/// Same as `decode_u64`, but tell why decoding failed.
+#[cfg(test)]
+pub fn decode_u64_checked(ascii_num: &[u8], len: &mut usize) -> Result<u64, NumberError> {
+    let num = decode_u128_checked(ascii_num, len, false)?;
+    return u64::try_from(num).map_err(|_| NumberError::Overflow);
+}
+
+/// Decode slice to i128.
+/// This function does not allow number starting with '+' unless `lenient` is true.
+/// If `lenient` is true, a leading `+`, leading zeros and `-0` are allowed as well.
 ///
 /// # Arguments
 ///
 /// * `ascii_num` - slice to decode
 /// * `len` - the length of decoded ascii number (if decoding fails it means max length of correct ascii numbers)
+/// * `lenient` - whether to allow a leading `+`, leading zeros and `-0`
 ///
 /// # Example
 ///
 /// For convenience, below examples use `ascii_num` as `&str`.
 ///
-/// - `decode_u64("1234", &mut len)` returns `Some(1234)` and `len` must be 4.
-/// - `decode_u64("1234+1234", &mut len)` returns `Some(1234)` and len must be 4.
-/// - `decode_u64("1234abcd", &mut len)` returns `Some(1234)` and len must be 4.
-/// - `decode_u64("-1234", &mut len)` returns `Some(-1234)` and `len` must be 5.
-/// - `decode_u64("+1234", &mut len)` returns `None` and `len` must be 0. Note that this functions does not allow number starting with '+'.
-/// - `decode_u64("9223372036854775807", &mut len)` returns `Some(9223372036854775807)` and `len` must be 19. Note that `9223372036854775807` is `i64::MAX`.
-/// - `decode_u64("-9223372036854775808", &mut len)` returns `Some(-9223372036854775808)` and `len` must be 20. Note that `-9223372036854775808` is `i64::MIN`.
-/// - `decode_u64("9223372036854775808", &mut len)` returns `None` and `len` must be 19. Note that `9223372036854775807` is `i64::MAX + 1`.
-/// - `decode_u64("-9223372036854775809", &mut len)` returns `None` and `len` must be 20. Note that `-9223372036854775809` is `i64::MIN - 1`.
-pub fn decode_i64(ascii_num: &[u8], len: &mut usize) -> Option<i64> {
-    if ascii_num.len() == 0 {
-        return None;
+/// - `decode_i128_checked("1234", &mut len, false)` returns `Ok(1234)` and `len` must be 4.
+/// - `decode_i128_checked("1234+1234", &mut len, false)` returns `Ok(1234)` and len must be 4.
+/// - `decode_i128_checked("1234abcd", &mut len, false)` returns `Ok(1234)` and len must be 4.
+/// - `decode_i128_checked("-1234", &mut len, false)` returns `Ok(-1234)` and `len` must be 5.
+/// - `decode_i128_checked("+1234", &mut len, false)` returns `Err(NumberError::NoDigits)` and `len` must be 0.
+/// - `decode_i128_checked("-0", &mut len, false)` returns `Err(NumberError::NegativeZero)` and `len` must be 2.
+/// - `decode_i128_checked` returns `Err(NumberError::Overflow)` for numbers above `i128::MAX` or below `i128::MIN`, and `len` must be past all digits.
+pub fn decode_i128_checked(
+    ascii_num: &[u8],
+    len: &mut usize,
+    lenient: bool,
+) -> Result<i128, NumberError> {
+    if ascii_num.is_empty() {
+        return Err(NumberError::NoDigits);
     }
 
     let is_positive;
@@ -79,47 +111,55 @@ pub fn decode_i64(ascii_num: &[u8], len: &mut usize) -> Option<i64> {
     if ascii_num[0] == b'-' {
         is_positive = false;
         start_offs = 1;
+    } else if lenient && ascii_num[0] == b'+' {
+        is_positive = true;
+        start_offs = 1;
     } else {
         is_positive = true;
         start_offs = 0;
     }
 
-    let opt_num = decode_u64(&ascii_num[start_offs..], len);
+    let res_num = decode_u128_checked(&ascii_num[start_offs..], len, lenient);
     *len += start_offs;
-    let num;
-    match opt_num {
-        Some(x) => {
-            num = x;
-        }
-        None => {
-            return None;
-        }
-    }
+    let num = res_num?;
 
-    if is_positive == true {
-        if num <= i64::MAX as u64 {
-            return Some(num as i64);
+    if is_positive {
+        if num <= i128::MAX as u128 {
+            return Ok(num as i128);
         } else {
-            return None;
+            return Err(NumberError::Overflow);
         }
     } else {
-        match num.cmp(&(i64::MIN as u64)) {
-            std::cmp::Ordering::Less => {
-                if num == 0 {
-                    return None;
+        match num.cmp(&(i128::MIN as u128)) {
+            core::cmp::Ordering::Less => {
+                if num == 0 && !lenient {
+                    return Err(NumberError::NegativeZero);
                 }
-                return Some(-(num as i64));
+                return Ok(-(num as i128));
             }
-            std::cmp::Ordering::Equal => {
-                return Some(i64::MIN);
+            core::cmp::Ordering::Equal => {
+                return Ok(i128::MIN);
             }
-            std::cmp::Ordering::Greater => {
-                return None;
+            core::cmp::Ordering::Greater => {
+                return Err(NumberError::Overflow);
             }
         }
     }
 }
 
+/// `decode_i128_checked` without telling why decoding failed, for tests.
+#[cfg(test)]
+pub fn decode_i128(ascii_num: &[u8], len: &mut usize, lenient: bool) -> Option<i128> {
+    return decode_i128_checked(ascii_num, len, lenient).ok();
+}
+
+/// `decode_i128_checked` for i64, which tests use to pin the i64 boundary.
+/// If `lenient` is true, a leading `+` is accepted as well (e.g. `"+5"` returns `Some(5)`).
+#[cfg(test)]
+pub fn decode_i64(ascii_num: &[u8], len: &mut usize, lenient: bool) -> Option<i64> {
+    return i64::try_from(decode_i128_checked(ascii_num, len, lenient).ok()?).ok();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -195,13 +235,25 @@ mod tests {
             let s = "0abcd";
             helper(s, Some(0), 1);
         }
+
+        #[test]
+        fn decode_u64_11() {
+            // Every number starting with `0` stops right after it.
+            for s in ["0", "00", "007", "0:", "0x"] {
+                let mut len: usize = 0;
+                assert_eq!(decode_u64(s.as_bytes(), &mut len), Some(0), "{}", s);
+                assert_eq!(len, 1, "{}", s);
+            }
+            helper("", None, 0);
+            helper(":", None, 0);
+        }
     }
     mod decode_i64_test {
         use super::*;
 
         fn helper(input: &str, expect: Option<i64>, expected_len: usize) {
             let mut len: usize = 0;
-            let result = decode_i64(input.as_bytes(), &mut len);
+            let result = decode_i64(input.as_bytes(), &mut len, false);
             assert_eq!(result, expect);
             assert_eq!(len, expected_len);
         }
@@ -293,5 +345,131 @@ mod tests {
             let s = "--+1234";
             helper(s, None, 1);
         }
+
+        #[test]
+        fn decode_i64_16() {
+            // A leading `+` is only accepted in lenient mode.
+            let mut len: usize = 0;
+            assert_eq!(decode_i64(b"+5", &mut len, true), Some(5));
+            assert_eq!(len, 2);
+            assert_eq!(decode_i64(b"+5", &mut len, false), None);
+            assert_eq!(len, 0);
+        }
+    }
+
+    mod decode_i128_test {
+        use super::*;
+
+        fn helper(input: &str, lenient: bool, expect: Option<i128>, expected_len: usize) {
+            let mut len: usize = 0;
+            let result = decode_i128(input.as_bytes(), &mut len, lenient);
+            assert_eq!(result, expect);
+            assert_eq!(len, expected_len);
+        }
+
+        #[test]
+        fn decode_i128_01() {
+            let ss = i128::MIN.to_string();
+            helper(&ss, false, Some(i128::MIN), ss.len());
+            let ss = i128::MAX.to_string();
+            helper(&ss, false, Some(i128::MAX), ss.len());
+        }
+
+        #[test]
+        fn decode_i128_02() {
+            helper("+12", false, None, 0);
+            helper("+12", true, Some(12), 3);
+            helper("0012", false, Some(0), 1);
+            helper("0012", true, Some(12), 4);
+            helper("-0", false, None, 2);
+            helper("-0", true, Some(0), 2);
+            helper("+", true, None, 1);
+        }
+
+        #[test]
+        fn decode_i128_03() {
+            helper("007", true, Some(7), 3);
+            helper("00", true, Some(0), 2);
+            helper("-00", false, None, 2);
+            helper("-007", false, None, 2);
+        }
+    }
+
+    mod decode_number_checked_test {
+        use super::*;
+
+        #[test]
+        fn decode_number_checked_01() {
+            let mut len = 0;
+            assert_eq!(decode_u128_checked(b"12", &mut len, false), Ok(12));
+            assert_eq!(len, 2);
+            assert_eq!(decode_u128_checked(b"12e", &mut len, false), Ok(12));
+            assert_eq!(len, 2);
+            assert_eq!(
+                decode_u128_checked(b"", &mut len, false),
+                Err(NumberError::NoDigits)
+            );
+            assert_eq!(
+                decode_u128_checked(b"e", &mut len, false),
+                Err(NumberError::NoDigits)
+            );
+            assert_eq!(len, 0);
+        }
+
+        #[test]
+        fn decode_number_checked_02() {
+            // Overflow consumes every digit, so it is told apart from stopping at a non-digit.
+            let s = format!("{}0e", u128::MAX);
+            let mut len = 0;
+            assert_eq!(
+                decode_u128_checked(s.as_bytes(), &mut len, false),
+                Err(NumberError::Overflow)
+            );
+            assert_eq!(len, s.len() - 1);
+            let s = (u64::MAX as u128 + 1).to_string();
+            assert_eq!(
+                decode_u64_checked(s.as_bytes(), &mut len),
+                Err(NumberError::Overflow)
+            );
+            assert_eq!(len, s.len());
+
+            let s = format!("-{}", i128::MIN as u128 + 1);
+            assert_eq!(
+                decode_i128_checked(s.as_bytes(), &mut len, false),
+                Err(NumberError::Overflow)
+            );
+            assert_eq!(len, s.len());
+            assert_eq!(
+                decode_i128_checked(b"-0", &mut len, false),
+                Err(NumberError::NegativeZero)
+            );
+            assert_eq!(
+                decode_i128_checked(b"-", &mut len, false),
+                Err(NumberError::NoDigits)
+            );
+        }
+
+        #[test]
+        fn decode_number_checked_03() {
+            // Every number starting with `0` stops right after it.
+            for s in ["0", "00", "007", "0:", "0x"] {
+                let mut len: usize = 0;
+                assert_eq!(
+                    decode_u128_checked(s.as_bytes(), &mut len, false),
+                    Ok(0),
+                    "{}",
+                    s
+                );
+                assert_eq!(len, 1, "{}", s);
+            }
+            let mut len = 0;
+            for s in ["", ":"] {
+                assert_eq!(
+                    decode_u128_checked(s.as_bytes(), &mut len, false),
+                    Err(NumberError::NoDigits)
+                );
+                assert_eq!(len, 0);
+            }
+        }
     }
 }
@@ -0,0 +1,54 @@
+#![allow(clippy::needless_return)]
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use torrent::MetaInfo;
+
+/// Multi-file torrent with `piece_count` piece hashes and `file_count` files.
+fn large_torrent(piece_count: usize, file_count: usize) -> Vec<u8> {
+    let mut ret = b"d8:announce32:http://tracker.example.com:6969/13:creation datei1700000000e4:infod5:filesl"
+        .to_vec();
+    for i in 0..file_count {
+        let name = format!("file-{:06}.bin", i);
+        ret.extend_from_slice(
+            format!("d6:lengthi1000e4:pathl3:dir{}:{}ee", name.len(), name).as_bytes(),
+        );
+    }
+    ret.extend_from_slice(b"e4:name5:large12:piece lengthi262144e6:pieces");
+    ret.extend_from_slice(format!("{}:", piece_count * 20).as_bytes());
+    ret.extend((0..piece_count * 20).map(|x| (x * 31 % 251) as u8));
+    ret.extend_from_slice(b"ee");
+    return ret;
+}
+
+fn bench_large_torrent(c: &mut Criterion) {
+    // 50k files of 1000 bytes fit in 191 pieces of 256 KiB.
+    let input = large_torrent(191, 50_000);
+    assert_eq!(
+        MetaInfo::parse_fast(&input),
+        MetaInfo::from_u8_len_check(&input)
+    );
+    let pieces_input = large_torrent(200_000, 10);
+
+    let mut group = c.benchmark_group("large_torrent");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("from_u8", |b| {
+        b.iter(|| MetaInfo::from_u8(black_box(&input)))
+    });
+    group.bench_function("parse_fast", |b| {
+        b.iter(|| MetaInfo::parse_fast(black_box(&input)))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("many_pieces");
+    group.throughput(Throughput::Bytes(pieces_input.len() as u64));
+    group.bench_function("from_u8", |b| {
+        b.iter(|| MetaInfo::from_u8_len_check(black_box(&pieces_input)))
+    });
+    group.bench_function("parse_fast", |b| {
+        b.iter(|| MetaInfo::parse_fast(black_box(&pieces_input)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_large_torrent);
+criterion_main!(benches);
@@ -0,0 +1,140 @@
+/// Set of pieces, one bit per piece.
+/// Bits are stored MSB-first like the payload of `bitfield` message of the peer protocol.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PieceBitfield {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl PieceBitfield {
+    /// Create bitfield of `len` pieces which are all unset.
+    pub fn new(len: usize) -> Self {
+        return PieceBitfield {
+            bytes: vec![0; len.div_ceil(8)],
+            len,
+        };
+    }
+
+    /// The number of pieces.
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    /// Set the bit of piece `index`. Out of range `index` is ignored.
+    pub fn set(&mut self, index: usize) {
+        if index < self.len {
+            self.bytes[index / 8] |= 0x80 >> (index % 8);
+        }
+    }
+
+    /// Whether piece `index` is set. Returns `false` for out of range `index`.
+    pub fn has(&self, index: usize) -> bool {
+        return index < self.len && self.bytes[index / 8] & (0x80 >> (index % 8)) != 0;
+    }
+
+    /// The number of set pieces.
+    pub fn count_ones(&self) -> usize {
+        return self.bytes.iter().map(|x| x.count_ones() as usize).sum();
+    }
+
+    /// Whether every piece is set.
+    pub fn is_complete(&self) -> bool {
+        return self.count_ones() == self.len;
+    }
+
+    /// Payload of `bitfield` message, where spare bits of the last byte are zero.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        return self.bytes.clone();
+    }
+
+    /// Read payload of `bitfield` message for `len` pieces.
+    /// Returns `None` if `bytes` is not `len` bits rounded up to bytes or a spare bit is set,
+    /// which peers must treat as a protocol error.
+    pub fn from_bytes(bytes: &[u8], len: usize) -> Option<Self> {
+        if bytes.len() != len.div_ceil(8) {
+            return None;
+        }
+        let spare_bits = bytes.len() * 8 - len;
+        if spare_bits > 0 && bytes[bytes.len() - 1] & (0xff >> (8 - spare_bits)) != 0 {
+            return None;
+        }
+        return Some(PieceBitfield {
+            bytes: bytes.to_vec(),
+            len,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod bitfield_test {
+        use super::*;
+
+        #[test]
+        fn bitfield_01() {
+            let bitfield = PieceBitfield::new(0);
+            assert!(bitfield.is_empty());
+            assert!(!bitfield.has(0));
+        }
+
+        #[test]
+        fn bitfield_02() {
+            let mut bitfield = PieceBitfield::new(10);
+            bitfield.set(0);
+            bitfield.set(9);
+            bitfield.set(10);
+            assert_eq!(bitfield.len(), 10);
+            assert!(bitfield.has(0));
+            assert!(!bitfield.has(1));
+            assert!(bitfield.has(9));
+            assert!(!bitfield.has(10));
+            assert_eq!(bitfield.bytes, vec![0x80, 0x40]);
+        }
+
+        #[test]
+        fn bitfield_03() {
+            let mut bitfield = PieceBitfield::new(3);
+            assert_eq!(bitfield.count_ones(), 0);
+            assert!(!bitfield.is_complete());
+            bitfield.set(0);
+            bitfield.set(1);
+            bitfield.set(1);
+            assert_eq!(bitfield.count_ones(), 2);
+            bitfield.set(2);
+            assert!(bitfield.is_complete());
+            assert!(PieceBitfield::new(0).is_complete());
+        }
+
+        #[test]
+        fn bitfield_04() {
+            let mut bitfield = PieceBitfield::new(10);
+            bitfield.set(1);
+            bitfield.set(9);
+            assert_eq!(bitfield.to_bytes(), vec![0x40, 0x40]);
+            assert_eq!(PieceBitfield::from_bytes(&[0x40, 0x40], 10), Some(bitfield));
+            assert_eq!(
+                PieceBitfield::from_bytes(&[0xff], 8).unwrap().count_ones(),
+                8
+            );
+            assert_eq!(
+                PieceBitfield::from_bytes(&[], 0),
+                Some(PieceBitfield::new(0))
+            );
+        }
+
+        #[test]
+        fn bitfield_05() {
+            // Wrong length or a spare bit set.
+            assert_eq!(PieceBitfield::from_bytes(&[0x40], 10), None);
+            assert_eq!(PieceBitfield::from_bytes(&[0x40, 0x00, 0x00], 10), None);
+            assert_eq!(PieceBitfield::from_bytes(&[0x40, 0x20], 10), None);
+            assert_eq!(PieceBitfield::from_bytes(&[0x01], 7), None);
+        }
+    }
+}
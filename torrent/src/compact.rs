@@ -0,0 +1,243 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+/// Node ID of DHT (BEP 5).
+pub type NodeId = [u8; 20];
+
+const PEER4_SIZE: usize = 6;
+const PEER6_SIZE: usize = 18;
+const NODE4_SIZE: usize = 20 + PEER4_SIZE;
+const NODE6_SIZE: usize = 20 + PEER6_SIZE;
+
+fn decode_addr4(bytes: &[u8]) -> SocketAddrV4 {
+    let ip = Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]);
+    let port = u16::from_be_bytes([bytes[4], bytes[5]]);
+    return SocketAddrV4::new(ip, port);
+}
+
+fn decode_addr6(bytes: &[u8]) -> SocketAddrV6 {
+    let mut octets = [0u8; 16];
+    octets.copy_from_slice(&bytes[..16]);
+    let port = u16::from_be_bytes([bytes[16], bytes[17]]);
+    return SocketAddrV6::new(Ipv6Addr::from(octets), port, 0, 0);
+}
+
+fn encode_addr4(addr: &SocketAddrV4, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&addr.ip().octets());
+    bytes.extend_from_slice(&addr.port().to_be_bytes());
+}
+
+fn encode_addr6(addr: &SocketAddrV6, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&addr.ip().octets());
+    bytes.extend_from_slice(&addr.port().to_be_bytes());
+}
+
+fn decode_node(bytes: &[u8]) -> NodeId {
+    let mut id = [0u8; 20];
+    id.copy_from_slice(&bytes[..20]);
+    return id;
+}
+
+/// Decode compact IPv4 peers (4 bytes of address and 2 bytes of big-endian port per peer).
+/// Returns `None` if the length is not a multiple of 6.
+pub fn decode_peers4(bytes: &[u8]) -> Option<Vec<SocketAddrV4>> {
+    if !bytes.len().is_multiple_of(PEER4_SIZE) {
+        return None;
+    }
+    return Some(bytes.chunks(PEER4_SIZE).map(decode_addr4).collect());
+}
+
+/// Encode IPv4 peers in the compact format read by `decode_peers4`.
+pub fn encode_peers4(peers: &[SocketAddrV4]) -> Vec<u8> {
+    let mut bytes = Vec::<u8>::with_capacity(peers.len() * PEER4_SIZE);
+    for peer in peers {
+        encode_addr4(peer, &mut bytes);
+    }
+    return bytes;
+}
+
+/// Decode compact IPv6 peers (16 bytes of address and 2 bytes of big-endian port per peer, BEP 7).
+/// Returns `None` if the length is not a multiple of 18.
+pub fn decode_peers6(bytes: &[u8]) -> Option<Vec<SocketAddrV6>> {
+    if !bytes.len().is_multiple_of(PEER6_SIZE) {
+        return None;
+    }
+    return Some(bytes.chunks(PEER6_SIZE).map(decode_addr6).collect());
+}
+
+/// Encode IPv6 peers in the compact format read by `decode_peers6`.
+pub fn encode_peers6(peers: &[SocketAddrV6]) -> Vec<u8> {
+    let mut bytes = Vec::<u8>::with_capacity(peers.len() * PEER6_SIZE);
+    for peer in peers {
+        encode_addr6(peer, &mut bytes);
+    }
+    return bytes;
+}
+
+/// Decode compact IPv4 nodes (20 bytes of node ID followed by a compact IPv4 peer, BEP 5).
+/// Returns `None` if the length is not a multiple of 26.
+pub fn decode_nodes4(bytes: &[u8]) -> Option<Vec<(NodeId, SocketAddrV4)>> {
+    if !bytes.len().is_multiple_of(NODE4_SIZE) {
+        return None;
+    }
+    return Some(
+        bytes
+            .chunks(NODE4_SIZE)
+            .map(|chk| (decode_node(chk), decode_addr4(&chk[20..])))
+            .collect(),
+    );
+}
+
+/// Encode IPv4 nodes in the compact format read by `decode_nodes4`.
+pub fn encode_nodes4(nodes: &[(NodeId, SocketAddrV4)]) -> Vec<u8> {
+    let mut bytes = Vec::<u8>::with_capacity(nodes.len() * NODE4_SIZE);
+    for (id, addr) in nodes {
+        bytes.extend_from_slice(id);
+        encode_addr4(addr, &mut bytes);
+    }
+    return bytes;
+}
+
+/// Decode compact IPv6 nodes (20 bytes of node ID followed by a compact IPv6 peer, BEP 32).
+/// Returns `None` if the length is not a multiple of 38.
+pub fn decode_nodes6(bytes: &[u8]) -> Option<Vec<(NodeId, SocketAddrV6)>> {
+    if !bytes.len().is_multiple_of(NODE6_SIZE) {
+        return None;
+    }
+    return Some(
+        bytes
+            .chunks(NODE6_SIZE)
+            .map(|chk| (decode_node(chk), decode_addr6(&chk[20..])))
+            .collect(),
+    );
+}
+
+/// Encode IPv6 nodes in the compact format read by `decode_nodes6`.
+pub fn encode_nodes6(nodes: &[(NodeId, SocketAddrV6)]) -> Vec<u8> {
+    let mut bytes = Vec::<u8>::with_capacity(nodes.len() * NODE6_SIZE);
+    for (id, addr) in nodes {
+        bytes.extend_from_slice(id);
+        encode_addr6(addr, &mut bytes);
+    }
+    return bytes;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer4() -> (Vec<u8>, SocketAddrV4) {
+        return (
+            vec![127, 0, 0, 1, 0x1a, 0xe1],
+            SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 6881),
+        );
+    }
+
+    fn peer6() -> (Vec<u8>, SocketAddrV6) {
+        let mut bytes = vec![0u8; 15];
+        bytes.extend_from_slice(&[1, 0x1a, 0xe1]);
+        return (bytes, SocketAddrV6::new(Ipv6Addr::LOCALHOST, 6881, 0, 0));
+    }
+
+    mod peers4_test {
+        use super::*;
+
+        #[test]
+        fn peers4_01() {
+            assert_eq!(decode_peers4(&[]), Some(vec![]));
+        }
+
+        #[test]
+        fn peers4_02() {
+            let (bytes, addr) = peer4();
+            let bytes = [bytes.clone(), bytes].concat();
+            assert_eq!(decode_peers4(&bytes), Some(vec![addr, addr]));
+            assert_eq!(encode_peers4(&[addr, addr]), bytes);
+        }
+
+        #[test]
+        fn peers4_03() {
+            let (bytes, _) = peer4();
+            assert_eq!(decode_peers4(&bytes[..5]), None);
+            assert_eq!(decode_peers4(&[bytes.as_slice(), &[0]].concat()), None);
+        }
+    }
+
+    mod peers6_test {
+        use super::*;
+
+        #[test]
+        fn peers6_01() {
+            assert_eq!(decode_peers6(&[]), Some(vec![]));
+        }
+
+        #[test]
+        fn peers6_02() {
+            let (bytes, addr) = peer6();
+            assert_eq!(decode_peers6(&bytes), Some(vec![addr]));
+            assert_eq!(encode_peers6(&[addr]), bytes);
+        }
+
+        #[test]
+        fn peers6_03() {
+            let (bytes, _) = peer6();
+            assert_eq!(decode_peers6(&bytes[..17]), None);
+            assert_eq!(decode_peers6(&bytes[..6]), None);
+        }
+    }
+
+    mod nodes4_test {
+        use super::*;
+
+        #[test]
+        fn nodes4_01() {
+            assert_eq!(decode_nodes4(&[]), Some(vec![]));
+        }
+
+        #[test]
+        fn nodes4_02() {
+            let (peer, addr) = peer4();
+            let bytes = [vec![0xab; 20], peer].concat();
+            assert_eq!(decode_nodes4(&bytes), Some(vec![([0xab; 20], addr)]));
+            assert_eq!(encode_nodes4(&[([0xab; 20], addr)]), bytes);
+        }
+
+        #[test]
+        fn nodes4_03() {
+            let (peer, _) = peer4();
+            let bytes = [vec![0xab; 20], peer].concat();
+            assert_eq!(decode_nodes4(&bytes[..25]), None);
+            assert_eq!(decode_nodes4(&bytes[..6]), None);
+        }
+    }
+
+    mod nodes6_test {
+        use super::*;
+
+        #[test]
+        fn nodes6_01() {
+            assert_eq!(decode_nodes6(&[]), Some(vec![]));
+        }
+
+        #[test]
+        fn nodes6_02() {
+            let (peer, addr) = peer6();
+            let bytes = [vec![0xcd; 20], peer.clone(), vec![0xef; 20], peer].concat();
+            assert_eq!(
+                decode_nodes6(&bytes),
+                Some(vec![([0xcd; 20], addr), ([0xef; 20], addr)])
+            );
+            assert_eq!(
+                encode_nodes6(&[([0xcd; 20], addr), ([0xef; 20], addr)]),
+                bytes
+            );
+        }
+
+        #[test]
+        fn nodes6_03() {
+            let (peer, _) = peer6();
+            let bytes = [vec![0xcd; 20], peer].concat();
+            assert_eq!(decode_nodes6(&bytes[..37]), None);
+            assert_eq!(decode_nodes6(&bytes[..18]), None);
+        }
+    }
+}
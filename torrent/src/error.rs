@@ -0,0 +1,72 @@
+use std::fmt;
+
+use bencode_decoder::DecodeError;
+
+/// Why parsing a torrent with `MetaInfo::from_u8` failed.
+/// Field names are the bencoded keys, e.g. `"piece length"`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MetaInfoError {
+    /// The input is not valid bencode.
+    Decode(DecodeError),
+    /// The top-level element is not a dictionary, e.g. a tracker response decoded as a list or an integer.
+    NotADictionary,
+    /// A required key is absent.
+    MissingField(&'static str),
+    /// A key holds a value of the wrong type, e.g. a string where an integer is required.
+    WrongType(&'static str),
+    /// `pieces` is not a multiple of 20 bytes.
+    BadPiecesLength,
+    /// `name` is not valid UTF-8.
+    InvalidName,
+    /// `piece length` is zero although there is content to split into pieces.
+    InvalidPieceLength,
+    /// `pieces` does not hold one hash per `piece length` bytes of content.
+    PieceCountMismatch { expected: u64, actual: usize },
+    /// The file lengths add up to more than `u64::MAX`.
+    TotalSizeOverflow,
+    /// `info` has both `length` of a single-file torrent and `files` of a multi-file torrent.
+    LengthAndFiles,
+    /// `info` has neither `length` nor `files` (nor only a v2 `file tree`).
+    NoLengthOrFiles,
+}
+
+impl fmt::Display for MetaInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetaInfoError::Decode(e) => write!(f, "invalid bencode: {}", e),
+            MetaInfoError::NotADictionary => {
+                write!(f, "top-level element is not a dictionary, so not a torrent")
+            }
+            MetaInfoError::MissingField(x) => write!(f, "missing field '{}'", x),
+            MetaInfoError::WrongType(x) => write!(f, "field '{}' has a wrong type", x),
+            MetaInfoError::BadPiecesLength => {
+                write!(f, "length of 'pieces' is not a multiple of 20")
+            }
+            MetaInfoError::InvalidName => write!(f, "field 'name' is not valid UTF-8"),
+            MetaInfoError::InvalidPieceLength => write!(f, "field 'piece length' is zero"),
+            MetaInfoError::PieceCountMismatch { expected, actual } => write!(
+                f,
+                "content needs {} pieces, but 'pieces' has {}",
+                expected, actual
+            ),
+            MetaInfoError::TotalSizeOverflow => write!(f, "total length of files overflows"),
+            MetaInfoError::LengthAndFiles => write!(f, "info has both 'length' and 'files'"),
+            MetaInfoError::NoLengthOrFiles => write!(f, "info has neither 'length' nor 'files'"),
+        }
+    }
+}
+
+impl std::error::Error for MetaInfoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MetaInfoError::Decode(e) => return Some(e),
+            _ => return None,
+        }
+    }
+}
+
+impl From<DecodeError> for MetaInfoError {
+    fn from(e: DecodeError) -> Self {
+        return MetaInfoError::Decode(e);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utils::{base32_decode, base32_encode, hex_decode, hex_encode};
+
+/// v1 info hash, the SHA-1 of the bencoded `info` dictionary, which identifies a torrent.
+/// Displayed as 40 lowercase hex characters.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, Default)]
+pub struct InfoHash(pub [u8; 20]);
+
+impl InfoHash {
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        return &self.0;
+    }
+
+    /// 40 lowercase hex characters, same as `to_string`.
+    pub fn to_hex(&self) -> String {
+        return hex_encode(&self.0);
+    }
+
+    /// 32 base32 characters, the other form accepted in `magnet:?xt=urn:btih:`.
+    pub fn to_base32(&self) -> String {
+        return base32_encode(&self.0);
+    }
+}
+
+impl From<[u8; 20]> for InfoHash {
+    fn from(x: [u8; 20]) -> Self {
+        return InfoHash(x);
+    }
+}
+
+impl fmt::Display for InfoHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.to_hex());
+    }
+}
+
+/// Why parsing an [`InfoHash`] from a string failed: it is neither 40 hex nor 32 base32 characters.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseInfoHashError;
+
+impl fmt::Display for ParseInfoHashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "info hash must be 40 hex or 32 base32 characters");
+    }
+}
+
+impl std::error::Error for ParseInfoHashError {}
+
+/// Parse 40 hex or 32 base32 characters of either case.
+impl FromStr for InfoHash {
+    type Err = ParseInfoHashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = match s.len() {
+            40 => hex_decode(s),
+            32 => base32_decode(s),
+            _ => None,
+        };
+        return bytes
+            .and_then(|x| <[u8; 20]>::try_from(x).ok())
+            .map(InfoHash)
+            .ok_or(ParseInfoHashError);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod info_hash_test {
+        use super::*;
+        use std::collections::HashMap;
+
+        const HEX: &str = "4de9b0e9855b349178fb7a42f37dc0f2fac3018d";
+
+        #[test]
+        fn info_hash_01() {
+            let info_hash: InfoHash = HEX.parse().unwrap();
+            assert_eq!(info_hash.to_string(), HEX);
+            assert_eq!(HEX.to_uppercase().parse::<InfoHash>(), Ok(info_hash));
+            assert_eq!(info_hash.to_base32().parse::<InfoHash>(), Ok(info_hash));
+            assert_eq!(
+                info_hash.to_base32().to_lowercase().parse::<InfoHash>(),
+                Ok(info_hash)
+            );
+        }
+
+        #[test]
+        fn info_hash_02() {
+            for s in [
+                "",
+                "4de9",
+                &HEX[..39],
+                &format!("{}zz", &HEX[..38]),
+                "1".repeat(32).as_str(),
+            ] {
+                assert_eq!(s.parse::<InfoHash>(), Err(ParseInfoHashError), "{}", s);
+            }
+        }
+
+        #[test]
+        fn info_hash_03() {
+            let mut torrents = HashMap::<InfoHash, &str>::new();
+            torrents.insert(InfoHash([1; 20]), "a");
+            torrents.insert(InfoHash::from([2; 20]), "b");
+            assert_eq!(torrents.get(&InfoHash([1; 20])), Some(&"a"));
+            assert_eq!(torrents.get(&InfoHash([3; 20])), None);
+        }
+    }
+}
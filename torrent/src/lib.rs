@@ -1 +1,19 @@
+#![allow(clippy::needless_return)]
+
+pub mod bitfield;
+pub mod compact;
+mod error;
+mod info_hash;
 mod meta_info;
+#[cfg(feature = "serde")]
+mod serde_impl;
+pub mod tracker;
+mod utils;
+
+pub use error::MetaInfoError;
+pub use info_hash::{InfoHash, ParseInfoHashError};
+pub use meta_info::{
+    CommonFileInfo, FileEntry, FileInfo, FilePieceRange, FileTree, FileTreeFile, MetaInfo,
+    MetaInfoBuilder, MultipleFileInfo, MultipleFileInfoFile, ParseOptions, PartialMetaInfo,
+    SingleFileInfo,
+};
@@ -1,21 +1,318 @@
+use core::ops::Range;
 use core::str;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use bencode_decoder::*;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::bitfield::PieceBitfield;
+use crate::error::MetaInfoError;
+use crate::info_hash::InfoHash;
+use crate::tracker::AnnounceRequest;
+use crate::utils::{hex_encode, url_decode, url_encode};
+
+/// Value of the required `key` in `dict`.
+fn field<'a>(
+    dict: &'a BTreeMap<Vec<u8>, Element>,
+    key: &'static str,
+) -> Result<&'a Element, MetaInfoError> {
+    return dict
+        .get(key.as_bytes())
+        .ok_or(MetaInfoError::MissingField(key));
+}
+
+/// Value of the required `key` in `dict` converted by `convert`, which returns `None` on a wrong type.
+fn typed_field<'a, T>(
+    dict: &'a BTreeMap<Vec<u8>, Element>,
+    key: &'static str,
+    convert: impl FnOnce(&'a Element) -> Option<T>,
+) -> Result<T, MetaInfoError> {
+    return convert(field(dict, key)?).ok_or(MetaInfoError::WrongType(key));
+}
+
+/// `name` of the `info` dictionary as `(name, raw name)`.
+/// `name.utf-8` is preferred when it is valid UTF-8, otherwise `name` itself must be valid UTF-8.
+/// The raw name is the bytes of `name`, which is in the creator's locale encoding in old torrents.
+fn name_field(info_dict: &BTreeMap<Vec<u8>, Element>) -> Result<(String, Vec<u8>), MetaInfoError> {
+    let name_utf8 = info_dict
+        .get(b"name.utf-8".as_slice())
+        .and_then(|x| x.convert_to_string());
+    let raw_name = match info_dict.get(b"name".as_slice()) {
+        Some(x) => Some(
+            x.convert_to_ref_vec_u8()
+                .ok_or(MetaInfoError::WrongType("name"))?
+                .clone(),
+        ),
+        None => None,
+    };
+    match (name_utf8, raw_name) {
+        (Some(name), raw_name) => {
+            let raw_name = raw_name.unwrap_or_else(|| name.as_bytes().to_vec());
+            return Ok((name, raw_name));
+        }
+        (None, Some(raw_name)) => {
+            let name =
+                String::from_utf8(raw_name.clone()).map_err(|_| MetaInfoError::InvalidName)?;
+            return Ok((name, raw_name));
+        }
+        (None, None) => return Err(MetaInfoError::MissingField("name")),
+    }
+}
+
+/// Name for an `info` dictionary without `name` nor `name.utf-8`, which is the only top-level entry
+/// of the v2 file tree, or else the first path component of the first file of a multi-file torrent.
+/// For a multi-file torrent, the name is only a candidate which `strip_default_name` must accept.
+fn default_name(
+    info_dict: &BTreeMap<Vec<u8>, Element>,
+    file_tree: Option<&FileTree>,
+) -> Option<String> {
+    if let Some(FileTree::Directory(entries)) = file_tree {
+        if entries.len() == 1 {
+            return entries.keys().next().cloned();
+        }
+    }
+    let first_file = info_dict.get(b"files".as_slice())?.iter_list()?.next()?;
+    let (path, _) = path_field(first_file.convert_to_ref_dict()?).ok()?;
+    return path.into_iter().next();
+}
+
+/// Read access to both owned `Element` and borrowed `ElementRef`, so that `MetaInfo::from_u8` and
+/// `MetaInfo::parse_fast` share one parser for the entries of `files` and cannot drift apart.
+trait BencodeValue: Sized {
+    type Dict: BencodeDict<Value = Self>;
+
+    fn bytes(&self) -> Option<&[u8]>;
+    fn integer(&self) -> Option<i64>;
+    fn list(&self) -> Option<&[Self]>;
+    fn dict(&self) -> Option<&Self::Dict>;
+
+    fn string(&self) -> Option<&str> {
+        return str::from_utf8(self.bytes()?).ok();
+    }
+}
+
+trait BencodeDict {
+    type Value: BencodeValue<Dict = Self>;
+
+    fn value(&self, key: &str) -> Option<&Self::Value>;
+}
+
+impl BencodeValue for Element {
+    type Dict = BTreeMap<Vec<u8>, Element>;
+
+    fn bytes(&self) -> Option<&[u8]> {
+        return self.as_bytes();
+    }
+
+    fn integer(&self) -> Option<i64> {
+        return self.convert_to_i64();
+    }
+
+    fn list(&self) -> Option<&[Self]> {
+        return self.convert_to_ref_list().map(Vec::as_slice);
+    }
+
+    fn dict(&self) -> Option<&Self::Dict> {
+        return self.convert_to_ref_dict();
+    }
+}
+
+impl BencodeDict for BTreeMap<Vec<u8>, Element> {
+    type Value = Element;
+
+    fn value(&self, key: &str) -> Option<&Element> {
+        return self.get(key.as_bytes());
+    }
+}
+
+impl<'a> BencodeValue for ElementRef<'a> {
+    type Dict = BTreeMap<&'a [u8], ElementRef<'a>>;
+
+    fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            ElementRef::ByteString(x) => return Some(x),
+            _ => return None,
+        }
+    }
+
+    fn integer(&self) -> Option<i64> {
+        match self {
+            ElementRef::Integer(x) => return Some(*x),
+            _ => return None,
+        }
+    }
+
+    fn list(&self) -> Option<&[Self]> {
+        match self {
+            ElementRef::List(x) => return Some(x),
+            _ => return None,
+        }
+    }
+
+    fn dict(&self) -> Option<&Self::Dict> {
+        match self {
+            ElementRef::Dictionary(x) => return Some(x),
+            _ => return None,
+        }
+    }
+}
+
+impl<'a> BencodeDict for BTreeMap<&'a [u8], ElementRef<'a>> {
+    type Value = ElementRef<'a>;
+
+    fn value(&self, key: &str) -> Option<&ElementRef<'a>> {
+        return self.get(key.as_bytes());
+    }
+}
+
+/// Remove the name from the front of every path of `info`, after it was taken from there by `default_name`.
+/// Keeping it would save the content under `name/name/...`. Return false, leaving `info` untouched, unless
+/// every file lies under the name, since there is no name to use otherwise.
+fn strip_default_name(info: &mut FileInfo) -> bool {
+    let FileInfo::MultipleFile(info) = info else {
+        return true;
+    };
+    let name = &info.name;
+    if !info
+        .files
+        .iter()
+        .all(|file| file.path.len() >= 2 && file.path[0] == *name && !file.raw_path.is_empty())
+    {
+        return false;
+    }
+    for file in info.files.iter_mut() {
+        file.path.remove(0);
+        file.raw_path.remove(0);
+    }
+    return true;
+}
+
+/// `path` of a file as `(path, raw path)`, preferring `path.utf-8` like `name_field`.
+fn path_field<D: BencodeDict>(file_dict: &D) -> Result<(Vec<String>, Vec<Vec<u8>>), MetaInfoError> {
+    let path_utf8: Option<Vec<String>> = file_dict
+        .value("path.utf-8")
+        .and_then(|x| x.list())
+        .and_then(|comps| {
+            comps
+                .iter()
+                .map(|x| Some(x.string()?.to_string()))
+                .collect()
+        });
+    let raw_path = match file_dict.value("path") {
+        Some(x) => Some(
+            x.list()
+                .and_then(|comps| comps.iter().map(|x| Some(x.bytes()?.to_vec())).collect())
+                .ok_or(MetaInfoError::WrongType("path"))?,
+        ),
+        None => None,
+    };
+    match (path_utf8, raw_path) {
+        (Some(path), raw_path) => {
+            let raw_path =
+                raw_path.unwrap_or_else(|| path.iter().map(|x| x.as_bytes().to_vec()).collect());
+            return Ok((path, raw_path));
+        }
+        (None, Some(raw_path)) => {
+            let path = raw_path
+                .iter()
+                .map(|x| String::from_utf8(x.clone()).ok())
+                .collect::<Option<Vec<String>>>()
+                .ok_or(MetaInfoError::WrongType("path"))?;
+            return Ok((path, raw_path));
+        }
+        (None, None) => return Err(MetaInfoError::MissingField("path")),
+    }
+}
+
+/// Only `private` of 1 marks a torrent private (BEP 27); anything else is treated like absent.
+fn private_field(info_dict: &BTreeMap<Vec<u8>, Element>) -> bool {
+    return info_dict
+        .get(b"private".as_slice())
+        .and_then(|x| x.convert_to_bool())
+        .unwrap_or(false);
+}
+
+fn source_field(info_dict: &BTreeMap<Vec<u8>, Element>) -> Option<String> {
+    return info_dict
+        .get(b"source".as_slice())
+        .and_then(|x| x.convert_to_string());
+}
+
+/// `announce-list` must be a list of tiers, each a list of tracker URLs.
+/// Empty URLs and then empty tiers are dropped, and nothing left is the same as no `announce-list`.
+fn parse_announce_list(element: &Element) -> Result<Option<Vec<Vec<String>>>, MetaInfoError> {
+    let tiers = element
+        .iter_list()
+        .and_then(|tiers| {
+            tiers
+                .map(|x| x.convert_to_string_list())
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or(MetaInfoError::WrongType("announce-list"))?;
+    let tiers: Vec<Vec<String>> = tiers
+        .into_iter()
+        .map(|tier| {
+            tier.into_iter()
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|tier| !tier.is_empty())
+        .collect();
+    return Ok(Some(tiers).filter(|x| !x.is_empty()));
+}
+
+/// `piece layers` maps 32-byte `pieces root`s to the concatenated 32-byte hashes of their pieces.
+fn parse_piece_layers(
+    element: &Element,
+) -> Result<BTreeMap<[u8; 32], Vec<[u8; 32]>>, MetaInfoError> {
+    let wrong_type = MetaInfoError::WrongType("piece layers");
+    let dict = element.convert_to_ref_dict().ok_or(wrong_type.clone())?;
+    let mut ret = BTreeMap::<[u8; 32], Vec<[u8; 32]>>::new();
+    for (key, val) in dict {
+        let root = <[u8; 32]>::try_from(key.as_slice()).map_err(|_| wrong_type.clone())?;
+        let hashes = val.convert_to_ref_vec_u8().ok_or(wrong_type.clone())?;
+        if !hashes.len().is_multiple_of(32) {
+            return Err(wrong_type);
+        }
+        let hashes = hashes
+            .chunks(32)
+            .map(|chk| <[u8; 32]>::try_from(chk).unwrap())
+            .collect();
+        ret.insert(root, hashes);
+    }
+    return Ok(ret);
+}
+
+/// Check that `comp` is a single plain file name, which can not lead out of its parent directory.
+fn is_safe_component(comp: &str) -> bool {
+    if comp.contains(['/', '\\', '\0']) {
+        return false;
+    }
+    match Path::new(comp).components().collect::<Vec<_>>().as_slice() {
+        [Component::Normal(x)] => return *x == comp,
+        _ => return false,
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord, Default)]
-pub(crate) struct CommonFileInfo {
+pub struct CommonFileInfo {
     piece_length: usize,
     pieces: Vec<Vec<u8>>,
     is_private: bool,
+    source: Option<String>,
 }
 
 #[allow(dead_code)]
 impl CommonFileInfo {
     const PIECE_HASH_SIZE: usize = 20;
 
-    pub fn new(piece_length: usize, pieces: &Vec<u8>, is_private: bool) -> Option<Self> {
-        if pieces.len() % 20 != 0 {
+    pub fn new(piece_length: usize, pieces: &[u8], is_private: bool) -> Option<Self> {
+        if !pieces.len().is_multiple_of(Self::PIECE_HASH_SIZE) {
             return None;
         } else {
             Some(CommonFileInfo {
@@ -25,6 +322,7 @@ impl CommonFileInfo {
                     .map(|chk| chk.to_vec())
                     .collect(),
                 is_private,
+                source: None,
             })
         }
     }
@@ -37,61 +335,133 @@ impl CommonFileInfo {
         }
     }
 
-    pub fn from_dict(info_dict: &HashMap<String, Element>) -> Option<Self> {
-        let piece_length = info_dict.get("piece length")?.convert_to_u64()? as usize;
-        let pieces = info_dict.get("pieces")?.convert_to_ref_vec_u8()?;
-        let is_private = match info_dict.get("private") {
-            Some(x) => {
-                if let Some(y) = x.convert_to_i64() {
-                    y == 1
-                } else {
-                    false
-                }
-            }
-            None => false,
-        };
-        Some(CommonFileInfo::new(piece_length, pieces, is_private)?)
+    pub fn from_dict(info_dict: &BTreeMap<Vec<u8>, Element>) -> Option<Self> {
+        CommonFileInfo::try_from_dict(info_dict).ok()
+    }
+
+    /// Same as `from_dict`, but tell why parsing failed.
+    pub fn try_from_dict(info_dict: &BTreeMap<Vec<u8>, Element>) -> Result<Self, MetaInfoError> {
+        let piece_length = typed_field(info_dict, "piece length", Element::convert_to_usize)?;
+        let pieces = typed_field(info_dict, "pieces", Element::as_bytes)?;
+        let mut ret = CommonFileInfo::new(piece_length, pieces, private_field(info_dict))
+            .ok_or(MetaInfoError::BadPiecesLength)?;
+        ret.source = source_field(info_dict);
+        return Ok(ret);
+    }
+
+    pub fn piece_length(&self) -> usize {
+        return self.piece_length;
+    }
+
+    /// SHA-1 hashes of the pieces, 20 bytes each.
+    pub fn pieces(&self) -> &[Vec<u8>] {
+        return &self.pieces;
+    }
+
+    pub fn is_private(&self) -> bool {
+        return self.is_private;
+    }
+
+    /// `source` tag set by private trackers so that cross-seeded torrents get distinct info hashes.
+    pub fn source(&self) -> Option<&str> {
+        return self.source.as_deref();
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord, Default)]
-pub(crate) struct SingleFileInfo {
+pub struct SingleFileInfo {
     common_file_info: CommonFileInfo,
     name: String,
+    raw_name: Vec<u8>,
     length: usize,
     md5sum: Option<String>,
 }
 
 #[allow(dead_code)]
 impl SingleFileInfo {
+    /// `info` of a single file named `name`, for creating torrents with [`MetaInfoBuilder`].
+    pub fn new(
+        common_file_info: CommonFileInfo,
+        name: &str,
+        length: usize,
+        md5sum: Option<&str>,
+    ) -> Self {
+        return SingleFileInfo {
+            common_file_info,
+            name: name.to_string(),
+            raw_name: name.as_bytes().to_vec(),
+            length,
+            md5sum: md5sum.map(|x| x.to_string()),
+        };
+    }
+
     pub fn new_with_common_info(
         common_file_info: CommonFileInfo,
-        info_dict: &HashMap<String, Element>,
+        info_dict: &BTreeMap<Vec<u8>, Element>,
     ) -> Option<Self> {
-        let name = info_dict.get("name")?.convert_to_str()?;
-        let length = info_dict.get("length")?.convert_to_u64()? as usize;
-        let md5sum = match info_dict.get("md5sum") {
+        SingleFileInfo::try_new_with_common_info(common_file_info, info_dict).ok()
+    }
+
+    /// Same as `new_with_common_info`, but tell why parsing failed.
+    pub fn try_new_with_common_info(
+        common_file_info: CommonFileInfo,
+        info_dict: &BTreeMap<Vec<u8>, Element>,
+    ) -> Result<Self, MetaInfoError> {
+        let (name, raw_name) = name_field(info_dict)?;
+        return SingleFileInfo::try_new_with_name(common_file_info, info_dict, name, raw_name);
+    }
+
+    fn try_new_with_name(
+        common_file_info: CommonFileInfo,
+        info_dict: &BTreeMap<Vec<u8>, Element>,
+        name: String,
+        raw_name: Vec<u8>,
+    ) -> Result<Self, MetaInfoError> {
+        let length = typed_field(info_dict, "length", Element::convert_to_usize)?;
+        let md5sum = match info_dict.get(b"md5sum".as_slice()) {
             Some(x) => x.convert_to_str(),
             None => None,
         };
 
-        Some(SingleFileInfo {
+        Ok(SingleFileInfo {
             common_file_info,
-            name: name.to_string(),
+            name,
+            raw_name,
             length,
-            md5sum: match md5sum {
-                Some(x) => Some(x.to_string()),
-                None => None,
-            },
+            md5sum: md5sum.map(|x| x.to_string()),
         })
     }
+
+    pub fn common_file_info(&self) -> &CommonFileInfo {
+        return &self.common_file_info;
+    }
+
+    /// File name of the content, from `name.utf-8` if present.
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Bytes of `name` as written in the torrent, which may be in a legacy encoding.
+    pub fn raw_name(&self) -> &[u8] {
+        return &self.raw_name;
+    }
+
+    pub fn length(&self) -> usize {
+        return self.length;
+    }
+
+    pub fn md5sum(&self) -> Option<&str> {
+        return self.md5sum.as_deref();
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord, Default)]
-pub(crate) struct MultipleFileInfoFile {
+pub struct MultipleFileInfoFile {
     length: usize,
     path: Vec<String>,
+    raw_path: Vec<Vec<u8>>,
     md5sum: Option<String>,
+    attr: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -99,11 +469,10 @@ impl MultipleFileInfoFile {
     pub fn new(length: usize, path: Vec<String>, md5sum: Option<&str>) -> Self {
         MultipleFileInfoFile {
             length,
+            raw_path: path.iter().map(|x| x.as_bytes().to_vec()).collect(),
             path,
-            md5sum: match md5sum {
-                Some(x) => Some(x.to_string()),
-                None => None,
-            },
+            md5sum: md5sum.map(|x| x.to_string()),
+            attr: None,
         }
     }
 
@@ -111,65 +480,435 @@ impl MultipleFileInfoFile {
         MultipleFileInfoFile::from_dict(info_element.convert_to_ref_dict()?)
     }
 
-    pub fn from_dict(info_dict: &HashMap<String, Element>) -> Option<Self> {
-        let length = info_dict.get("length")?.convert_to_u64()? as usize;
-        let path = info_dict.get("path")?.convert_to_string_list()?;
-        let md5sum = match info_dict.get("md5sum") {
-            Some(x) => x.convert_to_str(),
-            None => None,
-        };
-        Some(MultipleFileInfoFile::new(length, path, md5sum))
+    pub fn from_dict(info_dict: &BTreeMap<Vec<u8>, Element>) -> Option<Self> {
+        MultipleFileInfoFile::try_from_dict(info_dict).ok()
+    }
+
+    /// Same as `from_dict`, but tell why parsing failed.
+    pub fn try_from_dict(info_dict: &BTreeMap<Vec<u8>, Element>) -> Result<Self, MetaInfoError> {
+        return MultipleFileInfoFile::parse(info_dict);
+    }
+
+    /// `try_from_dict` for both owned and borrowed dictionaries.
+    fn parse<D: BencodeDict>(file_dict: &D) -> Result<Self, MetaInfoError> {
+        let length = file_dict
+            .value("length")
+            .ok_or(MetaInfoError::MissingField("length"))?
+            .integer()
+            .and_then(|x| usize::try_from(x).ok())
+            .ok_or(MetaInfoError::WrongType("length"))?;
+        let (path, raw_path) = path_field(file_dict)?;
+        let string = |key: &str| Some(file_dict.value(key)?.string()?.to_string());
+        return Ok(MultipleFileInfoFile {
+            length,
+            path,
+            raw_path,
+            md5sum: string("md5sum"),
+            attr: string("attr"),
+        });
+    }
+
+    pub fn length(&self) -> usize {
+        return self.length;
+    }
+
+    /// Path components relative to the directory named by `MultipleFileInfo::name`,
+    /// from `path.utf-8` if present.
+    pub fn path(&self) -> &[String] {
+        return &self.path;
+    }
+
+    /// Components of `path` as written in the torrent, which may be in a legacy encoding.
+    pub fn raw_path(&self) -> &[Vec<u8>] {
+        return &self.raw_path;
+    }
+
+    /// Join `path` to `base`, or return `None` if the path could escape `base`.
+    /// A component must not be empty, `.`, `..` or absolute, and must not hold a separator or NUL.
+    /// Always use this instead of `path` when writing downloaded data to disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - directory of the content, usually the download directory joined with the torrent name.
+    pub fn safe_path(&self, base: &Path) -> Option<PathBuf> {
+        if self.path.is_empty() {
+            return None;
+        }
+
+        let mut ret = base.to_path_buf();
+        for comp in &self.path {
+            if !is_safe_component(comp) {
+                return None;
+            }
+            ret.push(comp);
+        }
+        return Some(ret);
+    }
+
+    pub fn md5sum(&self) -> Option<&str> {
+        return self.md5sum.as_deref();
+    }
+
+    /// File attributes of BEP 47, one letter each: `p` padding, `x` executable, `h` hidden and `l` symlink.
+    pub fn attr(&self) -> Option<&str> {
+        return self.attr.as_deref();
+    }
+
+    /// Whether this is a padding file of BEP 47, which only aligns the next file to a piece boundary.
+    /// Its content is zeros, so it must be neither downloaded from peers nor shown or saved as a file.
+    pub fn is_padding(&self) -> bool {
+        return self.attr().is_some_and(|x| x.contains('p'));
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord, Default)]
-pub(crate) struct MultipleFileInfo {
+pub struct MultipleFileInfo {
     common_file_info: CommonFileInfo,
     name: String,
+    raw_name: Vec<u8>,
     files: Vec<MultipleFileInfoFile>,
 }
 
 #[allow(dead_code)]
 impl MultipleFileInfo {
-    pub fn new_with_common_info(
+    /// `info` of `files` in the directory `name`, for creating torrents with [`MetaInfoBuilder`].
+    pub fn new(
         common_file_info: CommonFileInfo,
         name: &str,
-        files_element: &Vec<Element>,
-    ) -> Option<Self> {
-        let mut info = MultipleFileInfo {
+        files: Vec<MultipleFileInfoFile>,
+    ) -> Self {
+        return MultipleFileInfo {
             common_file_info,
             name: name.to_string(),
-            files: Vec::<MultipleFileInfoFile>::with_capacity(files_element.len()),
+            raw_name: name.as_bytes().to_vec(),
+            files,
         };
+    }
 
-        for file in files_element {
-            let file_dict = file.convert_to_dict()?;
+    pub fn new_with_common_info(
+        common_file_info: CommonFileInfo,
+        name: &str,
+        files_element: &Vec<Element>,
+    ) -> Option<Self> {
+        MultipleFileInfo::try_new_with_common_info(common_file_info, name, files_element).ok()
+    }
 
-            let length = file_dict.get("length")?.convert_to_u64()? as usize;
-            let path = file_dict.get("path")?.convert_to_string_list()?;
-            let md5sum = match file_dict.get("md5sum") {
-                Some(x) => x.convert_to_str(),
-                None => None,
-            };
+    /// Same as `new_with_common_info`, but tell why parsing failed.
+    pub fn try_new_with_common_info(
+        common_file_info: CommonFileInfo,
+        name: &str,
+        files_element: &Vec<Element>,
+    ) -> Result<Self, MetaInfoError> {
+        let mut info = MultipleFileInfo::new(
+            common_file_info,
+            name,
+            Vec::<MultipleFileInfoFile>::with_capacity(files_element.len()),
+        );
 
+        for file in files_element {
+            let file_dict = file
+                .convert_to_ref_dict()
+                .ok_or(MetaInfoError::WrongType("files"))?;
             info.files
-                .push(MultipleFileInfoFile::new(length, path, md5sum));
+                .push(MultipleFileInfoFile::try_from_dict(file_dict)?);
         }
 
-        Some(info)
+        Ok(info)
+    }
+
+    pub fn common_file_info(&self) -> &CommonFileInfo {
+        return &self.common_file_info;
+    }
+
+    /// Directory name of the content, from `name.utf-8` if present.
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Bytes of `name` as written in the torrent, which may be in a legacy encoding.
+    pub fn raw_name(&self) -> &[u8] {
+        return &self.raw_name;
+    }
+
+    pub fn files(&self) -> &[MultipleFileInfoFile] {
+        return &self.files;
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
-pub(crate) enum FileInfo {
+pub enum FileInfo {
     SingleFile(SingleFileInfo),
     MultipleFile(MultipleFileInfo),
 }
 
+impl FileInfo {
+    pub fn common_file_info(&self) -> &CommonFileInfo {
+        match self {
+            FileInfo::SingleFile(x) => return &x.common_file_info,
+            FileInfo::MultipleFile(x) => return &x.common_file_info,
+        }
+    }
+
+    /// Sum of the file lengths, or `None` if it overflows `u64`, which only a malicious torrent does.
+    fn checked_total_size(&self) -> Option<u64> {
+        match self {
+            FileInfo::SingleFile(x) => return u64::try_from(x.length).ok(),
+            FileInfo::MultipleFile(x) => {
+                return x.files.iter().try_fold(0u64, |sum, file| {
+                    sum.checked_add(u64::try_from(file.length).ok()?)
+                });
+            }
+        }
+    }
+
+    fn common_file_info_mut(&mut self) -> &mut CommonFileInfo {
+        match self {
+            FileInfo::SingleFile(x) => return &mut x.common_file_info,
+            FileInfo::MultipleFile(x) => return &mut x.common_file_info,
+        }
+    }
+
+    /// File name of a single-file torrent or directory name of a multi-file torrent.
+    pub fn name(&self) -> &str {
+        match self {
+            FileInfo::SingleFile(x) => return &x.name,
+            FileInfo::MultipleFile(x) => return &x.name,
+        }
+    }
+
+    /// Same as `name`, but the bytes of `name` as written in the torrent.
+    pub fn raw_name(&self) -> &[u8] {
+        match self {
+            FileInfo::SingleFile(x) => return &x.raw_name,
+            FileInfo::MultipleFile(x) => return &x.raw_name,
+        }
+    }
+
+    /// Build the `info` dictionary holding exactly the keys this type knows about.
+    fn to_element(&self) -> Element {
+        let mut dict = BTreeMap::<Vec<u8>, Element>::new();
+        let common_file_info = self.common_file_info();
+        dict.insert(b"name".to_vec(), Element::from(self.raw_name().to_vec()));
+        if self.raw_name() != self.name().as_bytes() {
+            dict.insert(b"name.utf-8".to_vec(), Element::from(self.name()));
+        }
+        dict.insert(
+            b"piece length".to_vec(),
+            Element::from_i128(common_file_info.piece_length as i128),
+        );
+        dict.insert(
+            b"pieces".to_vec(),
+            Element::from(common_file_info.pieces.concat()),
+        );
+        if common_file_info.is_private {
+            dict.insert(b"private".to_vec(), Element::from(1));
+        }
+        if let Some(x) = &common_file_info.source {
+            dict.insert(b"source".to_vec(), Element::from(x.as_str()));
+        }
+
+        let insert_file =
+            |dict: &mut BTreeMap<Vec<u8>, Element>, length: usize, md5sum: &Option<String>| {
+                dict.insert(b"length".to_vec(), Element::from_i128(length as i128));
+                if let Some(x) = md5sum {
+                    dict.insert(b"md5sum".to_vec(), Element::from(x.as_str()));
+                }
+            };
+        match self {
+            FileInfo::SingleFile(x) => insert_file(&mut dict, x.length, &x.md5sum),
+            FileInfo::MultipleFile(x) => {
+                let files = x
+                    .files
+                    .iter()
+                    .map(|file| {
+                        let mut file_dict = BTreeMap::<Vec<u8>, Element>::new();
+                        insert_file(&mut file_dict, file.length, &file.md5sum);
+                        if let Some(x) = &file.attr {
+                            file_dict.insert(b"attr".to_vec(), Element::from(x.as_str()));
+                        }
+                        let raw_path = file.raw_path.iter().map(|x| Element::from(x.clone()));
+                        file_dict.insert(b"path".to_vec(), Element::List(raw_path.collect()));
+                        if file
+                            .raw_path
+                            .iter()
+                            .ne(file.path.iter().map(|x| x.as_bytes()))
+                        {
+                            let path = file.path.iter().map(|x| Element::from(x.as_str()));
+                            file_dict.insert(b"path.utf-8".to_vec(), Element::List(path.collect()));
+                        }
+                        Element::Dictionary(file_dict)
+                    })
+                    .collect();
+                dict.insert(b"files".to_vec(), Element::List(files));
+            }
+        }
+        return Element::Dictionary(dict);
+    }
+}
+
+/// A file of a v2 `file tree` (BEP 52).
+#[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord, Default)]
+pub struct FileTreeFile {
+    length: usize,
+    pieces_root: Option<[u8; 32]>,
+}
+
+impl FileTreeFile {
+    pub fn length(&self) -> usize {
+        return self.length;
+    }
+
+    /// Root of the SHA-256 Merkle tree over the blocks of the file. Empty files have none.
+    pub fn pieces_root(&self) -> Option<&[u8; 32]> {
+        return self.pieces_root.as_ref();
+    }
+}
+
+/// v2 `file tree` (BEP 52): directories map names to sub-trees, and a file is the leaf under the key `""`.
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
-pub struct MetaInfo {
+pub enum FileTree {
+    File(FileTreeFile),
+    Directory(BTreeMap<String, FileTree>),
+}
+
+impl FileTree {
+    pub fn try_from_element(element: &Element) -> Result<Self, MetaInfoError> {
+        let dict = element
+            .convert_to_ref_dict()
+            .ok_or(MetaInfoError::WrongType("file tree"))?;
+        if let Some(file) = dict.get(b"".as_slice()) {
+            let file_dict = file
+                .convert_to_ref_dict()
+                .ok_or(MetaInfoError::WrongType("file tree"))?;
+            let length = typed_field(file_dict, "length", Element::convert_to_usize)?;
+            let pieces_root = match file_dict.get(b"pieces root".as_slice()) {
+                Some(x) => Some(
+                    x.convert_to_ref_vec_u8()
+                        .and_then(|x| <[u8; 32]>::try_from(x.as_slice()).ok())
+                        .ok_or(MetaInfoError::WrongType("pieces root"))?,
+                ),
+                None => None,
+            };
+            return Ok(FileTree::File(FileTreeFile {
+                length,
+                pieces_root,
+            }));
+        }
+
+        let mut entries = BTreeMap::<String, FileTree>::new();
+        for (key, val) in dict {
+            let name = String::from_utf8(key.clone())
+                .map_err(|_| MetaInfoError::WrongType("file tree"))?;
+            entries.insert(name, FileTree::try_from_element(val)?);
+        }
+        return Ok(FileTree::Directory(entries));
+    }
+
+    /// Files under this tree with their path components, in the order of the tree.
+    pub fn files(&self) -> Vec<(Vec<String>, &FileTreeFile)> {
+        let mut ret = Vec::<(Vec<String>, &FileTreeFile)>::new();
+        self.collect_files(&mut Vec::<String>::new(), &mut ret);
+        return ret;
+    }
+
+    fn collect_files<'a>(
+        &'a self,
+        path: &mut Vec<String>,
+        ret: &mut Vec<(Vec<String>, &'a FileTreeFile)>,
+    ) {
+        match self {
+            FileTree::File(x) => ret.push((path.clone(), x)),
+            FileTree::Directory(x) => {
+                for (name, sub_tree) in x {
+                    path.push(name.clone());
+                    sub_tree.collect_files(path, ret);
+                    path.pop();
+                }
+            }
+        }
+    }
+
+    /// Lay the files out like a v1 torrent named `name`, for torrents which only have the v2 file tree.
+    /// The result has no piece hashes, since v2 hashes files with `pieces root` instead.
+    fn to_file_info(&self, name: &str, common_file_info: CommonFileInfo) -> FileInfo {
+        let files = self.files();
+        if let [(path, file)] = files.as_slice() {
+            if *path == [name] {
+                return FileInfo::SingleFile(SingleFileInfo::new(
+                    common_file_info,
+                    name,
+                    file.length,
+                    None,
+                ));
+            }
+        }
+        let files = files
+            .into_iter()
+            .map(|(path, file)| MultipleFileInfoFile::new(file.length, path, None))
+            .collect();
+        return FileInfo::MultipleFile(MultipleFileInfo::new(common_file_info, name, files));
+    }
+}
+
+/// Additional checks and clean-ups performed by [`MetaInfo::from_u8_with_options`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ParseOptions {
+    /// Reject multi-file torrents listing several files at the same path.
+    pub reject_path_collisions: bool,
+    /// Compare paths case-insensitively when detecting collisions (for case-insensitive file systems).
+    pub case_insensitive_paths: bool,
+    /// Keep only the first occurrence of a tracker appearing in several tiers of `announce-list`.
+    pub dedup_announce_list: bool,
+}
+
+/// What a magnet link tells about a torrent, returned by [`MetaInfo::from_magnet`].
+/// The info dictionary itself has to be fetched from peers using `info_hash`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PartialMetaInfo {
+    pub info_hash: InfoHash,
+    /// Display name from `dn`.
+    pub name: Option<String>,
+    /// Trackers from every `tr`, in order of appearance.
+    pub trackers: Vec<String>,
+}
+
+/// Where a file lies in the piece stream, returned by [`MetaInfo::file_piece_layout`].
+/// The file starts at `first_piece_offset` in piece `start_piece` and ends right before `last_piece_end`
+/// in piece `end_piece - 1`. An empty file has `start_piece == end_piece` and
+/// `first_piece_offset == last_piece_end`, pointing at where it would lie.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FilePieceRange {
+    /// Index of the file in `files`, or 0 for a single-file torrent.
+    pub file_index: usize,
+    pub start_piece: usize,
+    /// One past the last piece holding bytes of the file.
+    pub end_piece: usize,
+    pub first_piece_offset: usize,
+    pub last_piece_end: usize,
+}
+
+/// A file of the content, returned by [`MetaInfo::files`] for both single- and multi-file torrents.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileEntry {
+    /// Path starting with `name`, which is the whole path of a single-file torrent.
+    pub path: Vec<String>,
+    pub length: u64,
+    pub md5sum: Option<String>,
+    /// Padding file of BEP 47, which counts for pieces and offsets but is not real content.
+    pub is_padding: bool,
+}
+
+/// Build a [`MetaInfo`] with optional keys, for creating torrents.
+/// Serialize the result with [`MetaInfo::to_bencode`] or [`MetaInfo::write_to_file`].
+///
+/// # Example
+///
+/// - `MetaInfoBuilder::new(info).announce("http://t/a").comment("hi").private(true).build()`,
+///   where `info` is made with [`SingleFileInfo::new`] or [`MultipleFileInfo::new`].
+#[derive(Debug, Clone)]
+pub struct MetaInfoBuilder {
     info: FileInfo,
-    announce: String,
+    announce: Option<String>,
     announce_list: Option<Vec<Vec<String>>>,
     creation_date: Option<u64>,
     comment: Option<String>,
@@ -177,86 +916,3612 @@ pub struct MetaInfo {
     encoding: Option<String>,
 }
 
-#[allow(dead_code)]
-impl MetaInfo {
-    pub fn new(info: FileInfo, announce: &str) -> Self {
-        MetaInfo {
+impl MetaInfoBuilder {
+    pub fn new(info: FileInfo) -> Self {
+        return MetaInfoBuilder {
             info,
-            announce: announce.to_string(),
+            announce: None,
             announce_list: None,
             creation_date: None,
             comment: None,
             created_by: None,
             encoding: None,
-        }
+        };
     }
-}
 
-#[allow(dead_code)]
-impl MetaInfo {
-    pub fn from_element(element: &Element) -> Option<MetaInfo> {
-        let hashmap;
-        if let Element::Dictionary(x) = element {
-            hashmap = x;
-        } else {
-            return None;
-        }
+    pub fn announce(mut self, announce: &str) -> Self {
+        self.announce = Some(announce.to_string());
+        return self;
+    }
 
-        let announce = hashmap.get("announce")?.convert_to_str()?;
+    /// Tiers of trackers (BEP 12). Empty tiers are left out.
+    pub fn announce_list(mut self, announce_list: Vec<Vec<String>>) -> Self {
+        let tiers: Vec<Vec<String>> = announce_list
+            .into_iter()
+            .filter(|x| !x.is_empty())
+            .collect();
+        self.announce_list = Some(tiers).filter(|x| !x.is_empty());
+        return self;
+    }
 
-        let info_dict = hashmap.get("info")?.convert_to_dict()?;
-        let common_file_info = CommonFileInfo::from_dict(&info_dict)?;
-        let name = info_dict.get("name")?.convert_to_str()?;
-        let info = match info_dict.get("files") {
-            Some(files) => {
-                let files = files.convert_to_ref_list()?;
-                let info = MultipleFileInfo::new_with_common_info(common_file_info, name, files)?;
-                FileInfo::MultipleFile(info)
-            }
-            None => FileInfo::SingleFile(SingleFileInfo::new_with_common_info(
-                common_file_info,
-                &info_dict,
+    /// Seconds since the Unix epoch.
+    pub fn creation_date(mut self, creation_date: u64) -> Self {
+        self.creation_date = Some(creation_date);
+        return self;
+    }
+
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comment = Some(comment.to_string());
+        return self;
+    }
+
+    pub fn created_by(mut self, created_by: &str) -> Self {
+        self.created_by = Some(created_by.to_string());
+        return self;
+    }
+
+    pub fn encoding(mut self, encoding: &str) -> Self {
+        self.encoding = Some(encoding.to_string());
+        return self;
+    }
+
+    /// Set `private` of the info dictionary (BEP 27), which changes the info hash.
+    pub fn private(mut self, is_private: bool) -> Self {
+        self.info.common_file_info_mut().is_private = is_private;
+        return self;
+    }
+
+    pub fn build(self) -> MetaInfo {
+        let mut ret = MetaInfo::new(self.info, self.announce.as_deref());
+        ret.announce_list = self.announce_list;
+        ret.creation_date = self.creation_date;
+        ret.comment = self.comment;
+        ret.created_by = self.created_by;
+        ret.encoding = self.encoding;
+        return ret;
+    }
+}
+
+/// `Ord` compares fields in declaration order, and `announce_list` tier by tier as plain lists.
+/// It only gives a stable order for sorting or `BTreeSet`; it says nothing about tracker priority.
+#[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
+pub struct MetaInfo {
+    info: FileInfo,
+    /// Absent for trackerless torrents, which rely on DHT (BEP 5).
+    announce: Option<String>,
+    announce_list: Option<Vec<Vec<String>>>,
+    creation_date: Option<u64>,
+    comment: Option<String>,
+    created_by: Option<String>,
+    encoding: Option<String>,
+    /// DHT bootstrap nodes as `(host, port)` (BEP 5).
+    nodes: Option<Vec<(String, u16)>>,
+    /// Web seed URLs from `url-list` (BEP 19).
+    url_list: Option<Vec<String>>,
+    /// Whether `url-list` was a single URL rather than a list, so that `to_bencode` keeps the form.
+    url_list_is_single: bool,
+    /// Seed URLs from `httpseeds` (BEP 17), which use a different protocol than `url_list`.
+    http_seeds: Option<Vec<String>>,
+    /// `meta version` of the info dictionary, which is 2 for v2 and hybrid torrents (BEP 52).
+    meta_version: Option<u64>,
+    /// v2 `file tree`, parsed only when `meta_version` is 2.
+    file_tree: Option<FileTree>,
+    /// v2 `piece layers`: per-file piece hashes keyed by `pieces root`.
+    piece_layers: Option<BTreeMap<[u8; 32], Vec<[u8; 32]>>>,
+    /// Bencoded `info` dictionary, kept as it is so that re-encoding does not change `info_hash`.
+    info_bytes: Vec<u8>,
+    /// SHA-1 of `info_bytes`.
+    info_hash: [u8; 20],
+    /// SHA-256 of `info_bytes`, only for v2 and hybrid torrents.
+    info_hash_v2: Option<[u8; 32]>,
+}
+
+#[allow(dead_code)]
+impl MetaInfo {
+    pub fn new(info: FileInfo, announce: Option<&str>) -> Self {
+        let mut ret = MetaInfo::without_info_bytes(info, announce);
+        ret.set_info_bytes(encode(&ret.info.to_element()));
+        return ret;
+    }
+
+    /// `new` leaving `info_bytes` and the info hashes empty for `set_info_bytes`, so that parsers
+    /// do not encode and hash an `info` dictionary which they are going to replace anyway.
+    fn without_info_bytes(info: FileInfo, announce: Option<&str>) -> Self {
+        MetaInfo {
+            info_hash: [0; 20],
+            info_hash_v2: None,
+            info_bytes: Vec::new(),
+            info,
+            announce: announce.map(|x| x.to_string()),
+            announce_list: None,
+            creation_date: None,
+            comment: None,
+            created_by: None,
+            encoding: None,
+            nodes: None,
+            url_list: None,
+            url_list_is_single: false,
+            http_seeds: None,
+            meta_version: None,
+            file_tree: None,
+            piece_layers: None,
+        }
+    }
+
+    pub fn info(&self) -> &FileInfo {
+        return &self.info;
+    }
+
+    /// Same as `info().name()`. It is `name.utf-8`, then `name`, and for torrents without either
+    /// the only top-level entry of the v2 file tree or the first path component of the first file.
+    pub fn name(&self) -> &str {
+        return self.info.name();
+    }
+
+    pub fn announce(&self) -> Option<&str> {
+        return self.announce.as_deref();
+    }
+
+    /// Tiers of trackers from `announce-list`, if present.
+    pub fn announce_list(&self) -> Option<&[Vec<String>]> {
+        return self.announce_list.as_deref();
+    }
+
+    /// Tiers of trackers to announce to, in order of preference (BEP 12).
+    /// This is `announce-list` with `announce` as a tier of its own in front when no tier lists it,
+    /// or just `announce` without `announce-list`.
+    pub fn tiers(&self) -> Vec<Vec<String>> {
+        return self.announce_tiers().map(<[String]>::to_vec).collect();
+    }
+
+    /// Same tiers as `tiers`, borrowed instead of cloned.
+    /// Trackers of a tier are in the order of the torrent, so shuffle them as BEP 12 asks before use.
+    pub fn announce_tiers(&self) -> impl Iterator<Item = &[String]> {
+        let tiers = self.announce_list.as_deref().unwrap_or_default();
+        let announce = self
+            .announce
+            .as_ref()
+            .filter(|x| !x.is_empty() && !tiers.iter().flatten().any(|tracker| tracker == *x));
+        return announce
+            .map(core::slice::from_ref)
+            .into_iter()
+            .chain(tiers.iter().map(Vec::as_slice));
+    }
+
+    /// `tiers` with the trackers of each tier shuffled, as BEP 12 asks clients to do once on startup.
+    /// `rng(n)` should return a random index below `n`, so any random number generator fits and tests can stay
+    /// deterministic. Larger values are reduced modulo `n` rather than trusted.
+    pub fn shuffled_announce(&self, rng: &mut impl FnMut(usize) -> usize) -> Vec<Vec<String>> {
+        let mut tiers = self.tiers();
+        for tier in tiers.iter_mut() {
+            // Fisher-Yates shuffle.
+            for i in (1..tier.len()).rev() {
+                tier.swap(i, rng(i + 1) % (i + 1));
+            }
+        }
+        return tiers;
+    }
+
+    /// Remove trackers already listed in an earlier tier of `announce-list`, and then empty tiers.
+    pub fn dedup_announce_list(&mut self) {
+        let Some(tiers) = &mut self.announce_list else {
+            return;
+        };
+        let mut seen = HashSet::<String>::new();
+        for tier in tiers.iter_mut() {
+            tier.retain(|x| seen.insert(x.clone()));
+        }
+        tiers.retain(|tier| !tier.is_empty());
+        if tiers.is_empty() {
+            self.announce_list = None;
+        }
+    }
+
+    /// Announce parameters of this torrent for a client which has downloaded nothing yet,
+    /// so `left` is `total_size`.
+    pub fn announce_request(&self, peer_id: [u8; 20], port: u16) -> AnnounceRequest {
+        return AnnounceRequest::new(self.info_hash(), peer_id, port, self.total_size());
+    }
+
+    /// Announce URLs of every tracker, tier by tier like `tiers`, to try in order as BEP 12 asks.
+    /// A trackerless torrent gives no tiers.
+    /// It takes an [`AnnounceRequest`] from `announce_request` rather than a separate set of parameters,
+    /// and gives a URL per tracker rather than one, since which tracker answers is only known when announcing.
+    pub fn announce_urls(&self, request: &AnnounceRequest) -> Vec<Vec<String>> {
+        return self
+            .tiers()
+            .iter()
+            .map(|tier| tier.iter().map(|tracker| request.to_url(tracker)).collect())
+            .collect();
+    }
+
+    /// Seconds since the Unix epoch.
+    pub fn creation_date(&self) -> Option<u64> {
+        return self.creation_date;
+    }
+
+    /// `creation_date` as a `SystemTime`, or `None` if it is absent or not representable.
+    pub fn creation_time(&self) -> Option<SystemTime> {
+        return UNIX_EPOCH.checked_add(Duration::from_secs(self.creation_date?));
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        return self.comment.as_deref();
+    }
+
+    pub fn created_by(&self) -> Option<&str> {
+        return self.created_by.as_deref();
+    }
+
+    pub fn encoding(&self) -> Option<&str> {
+        return self.encoding.as_deref();
+    }
+
+    pub fn nodes(&self) -> Option<&[(String, u16)]> {
+        return self.nodes.as_deref();
+    }
+
+    pub fn url_list(&self) -> Option<&[String]> {
+        return self.url_list.as_deref();
+    }
+
+    pub fn http_seeds(&self) -> Option<&[String]> {
+        return self.http_seeds.as_deref();
+    }
+
+    pub fn meta_version(&self) -> Option<u64> {
+        return self.meta_version;
+    }
+
+    pub fn file_tree(&self) -> Option<&FileTree> {
+        return self.file_tree.as_ref();
+    }
+
+    pub fn piece_layers(&self) -> Option<&BTreeMap<[u8; 32], Vec<[u8; 32]>>> {
+        return self.piece_layers.as_ref();
+    }
+
+    /// Total length of the content in bytes, i.e. the length of the concatenated piece stream.
+    /// Every entry of `files` counts, so BEP 47 padding files are included.
+    /// Parsing rejects torrents whose total overflows `u64`, so only a `MetaInfo` built by hand can hit
+    /// `u64::MAX`, where the total saturates.
+    pub fn total_size(&self) -> u64 {
+        return self.info.checked_total_size().unwrap_or(u64::MAX);
+    }
+
+    /// `total_size` without padding files (BEP 47), i.e. the size of the files actually saved.
+    pub fn content_size(&self) -> u64 {
+        match &self.info {
+            FileInfo::SingleFile(x) => return x.length as u64,
+            FileInfo::MultipleFile(x) => {
+                return x
+                    .files
+                    .iter()
+                    .filter(|file| !file.is_padding())
+                    .fold(0u64, |sum, file| sum.saturating_add(file.length as u64));
+            }
+        }
+    }
+
+    /// Number of pieces, i.e. the number of hashes in `pieces`.
+    pub fn piece_count(&self) -> usize {
+        return self.info.common_file_info().pieces.len();
+    }
+
+    pub fn piece_length(&self) -> usize {
+        return self.info.common_file_info().piece_length;
+    }
+
+    /// Indices of the pieces overlapping the bytes `offset..offset + len` of the content,
+    /// where multi-file content is the concatenation of all files.
+    /// An empty byte range or a zero piece length gives an empty range.
+    /// The result is not clamped to `piece_count`, so check it for ranges past the end of the content.
+    pub fn pieces_for_range(&self, offset: u64, len: u64) -> Range<usize> {
+        let piece_length = self.piece_length() as u64;
+        if piece_length == 0 || len == 0 {
+            return 0..0;
+        }
+        let end = offset.saturating_add(len);
+        let first = offset / piece_length;
+        let last = end / piece_length + u64::from(!end.is_multiple_of(piece_length));
+        let to_usize = |x: u64| usize::try_from(x).unwrap_or(usize::MAX);
+        return to_usize(first)..to_usize(last);
+    }
+
+    /// Every file of the content in order, so that callers need not match on `info`.
+    /// Paths of a multi-file torrent get `name` prepended, like where the files are saved.
+    /// Padding files are kept so that offsets add up; skip those with `is_padding` when downloading or listing.
+    pub fn files(&self) -> Vec<FileEntry> {
+        match &self.info {
+            FileInfo::SingleFile(x) => {
+                return vec![FileEntry {
+                    path: vec![x.name.clone()],
+                    length: x.length as u64,
+                    md5sum: x.md5sum.clone(),
+                    is_padding: false,
+                }];
+            }
+            FileInfo::MultipleFile(x) => {
+                return x
+                    .files
+                    .iter()
+                    .map(|file| FileEntry {
+                        path: [core::slice::from_ref(&x.name), file.path.as_slice()].concat(),
+                        length: file.length as u64,
+                        md5sum: file.md5sum.clone(),
+                        is_padding: file.is_padding(),
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    /// Offset in the content where each file of `files` begins, which is `[0]` for a single-file torrent.
+    /// Together with `pieces_for_range`, this maps between files and pieces when writing to disk.
+    /// Offsets saturate at `u64::MAX` like `total_size`, which only a `MetaInfo` built by hand can reach.
+    pub fn file_offsets(&self) -> Vec<u64> {
+        return self
+            .files()
+            .iter()
+            .scan(0u64, |offset, file| {
+                let ret = *offset;
+                *offset = offset.saturating_add(file.length);
+                return Some(ret);
+            })
+            .collect();
+    }
+
+    /// Pieces each file spans, in the order of the files, where multi-file content is the concatenation
+    /// of all files. Empty for a zero piece length or if the content is longer than `u64::MAX`.
+    pub fn file_piece_layout(&self) -> Vec<FilePieceRange> {
+        let piece_length = self.piece_length() as u64;
+        if piece_length == 0 {
+            return Vec::new();
+        }
+        let lengths: Vec<u64> = match &self.info {
+            FileInfo::SingleFile(x) => vec![x.length as u64],
+            FileInfo::MultipleFile(x) => x.files.iter().map(|file| file.length as u64).collect(),
+        };
+        let to_usize = |x: u64| usize::try_from(x).unwrap_or(usize::MAX);
+        let mut offset = 0u64;
+        let mut ret = Vec::<FilePieceRange>::with_capacity(lengths.len());
+        for (file_index, length) in lengths.into_iter().enumerate() {
+            let Some(end) = offset.checked_add(length) else {
+                return Vec::new();
+            };
+            let start_piece = offset / piece_length;
+            let first_piece_offset = offset % piece_length;
+            let (end_piece, last_piece_end) = if length == 0 {
+                (start_piece, first_piece_offset)
+            } else {
+                let end_piece = end.div_ceil(piece_length);
+                (end_piece, end - (end_piece - 1) * piece_length)
+            };
+            ret.push(FilePieceRange {
+                file_index,
+                start_piece: to_usize(start_piece),
+                end_piece: to_usize(end_piece),
+                first_piece_offset: to_usize(first_piece_offset),
+                last_piece_end: to_usize(last_piece_end),
+            });
+            offset = end;
+        }
+        return ret;
+    }
+
+    /// Whether `private` is 1 (BEP 27). Peers of a private torrent must only come from its trackers,
+    /// so clients must not use DHT, PEX or local peer discovery for it.
+    pub fn is_private(&self) -> bool {
+        return self.info.common_file_info().is_private;
+    }
+
+    /// `source` key of the info dictionary, used by private trackers for cross-seeding.
+    pub fn source(&self) -> Option<&str> {
+        return self.info.common_file_info().source();
+    }
+
+    /// Check that `pieces` has exactly one 20-byte hash per `piece length` bytes of content.
+    /// Parsing only checks the length of `pieces`, so this catches truncated or tampered torrents.
+    /// v2-only torrents have no `pieces` and always pass.
+    pub fn validate(&self) -> Result<(), MetaInfoError> {
+        if !self.is_v1() {
+            return Ok(());
+        }
+
+        let common_file_info = self.info.common_file_info();
+        let pieces = &common_file_info.pieces;
+        if pieces
+            .iter()
+            .any(|x| x.len() != CommonFileInfo::PIECE_HASH_SIZE)
+        {
+            return Err(MetaInfoError::BadPiecesLength);
+        }
+
+        let piece_length = common_file_info.piece_length as u64;
+        let total_size = self
+            .info
+            .checked_total_size()
+            .ok_or(MetaInfoError::TotalSizeOverflow)?;
+        if piece_length == 0 {
+            if total_size == 0 && pieces.is_empty() {
+                return Ok(());
+            }
+            return Err(MetaInfoError::InvalidPieceLength);
+        }
+        let expected = total_size.div_ceil(piece_length);
+        if expected != pieces.len() as u64 {
+            return Err(MetaInfoError::PieceCountMismatch {
+                expected,
+                actual: pieces.len(),
+            });
+        }
+        return Ok(());
+    }
+
+    /// SHA-1 of the `index`-th piece, or `None` if there is no such piece.
+    pub fn piece_hash(&self, index: usize) -> Option<&[u8]> {
+        return self
+            .info
+            .common_file_info()
+            .pieces
+            .get(index)
+            .map(|x| x.as_slice());
+    }
+
+    /// Whether `data` is the `index`-th piece: it must have the length of that piece, which is
+    /// `last_piece_length` for the last one and `piece_length` otherwise, and match its SHA-1.
+    /// Return `false` if there is no such piece.
+    pub fn verify_piece(&self, index: usize, data: &[u8]) -> bool {
+        let Some(hash) = self.piece_hash(index) else {
+            return false;
+        };
+        let expected_len = if index + 1 == self.piece_count() {
+            self.last_piece_length()
+        } else {
+            self.piece_length()
+        };
+        return data.len() == expected_len && Sha1::digest(data).as_slice() == hash;
+    }
+
+    /// Length of the last piece, which is shorter than `piece_length` unless it divides `total_size`.
+    /// Return 0 for a torrent without content or with a zero piece length.
+    pub fn last_piece_length(&self) -> usize {
+        let piece_length = self.piece_length() as u64;
+        let total_size = self.total_size();
+        if piece_length == 0 || total_size == 0 {
+            return 0;
+        }
+        match total_size % piece_length {
+            0 => return piece_length as usize,
+            x => return x as usize,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl MetaInfo {
+    pub fn from_element(element: &Element) -> Option<MetaInfo> {
+        return MetaInfo::try_from_element(element).ok();
+    }
+
+    /// Same as `from_element`, but tell why parsing failed.
+    pub fn try_from_element(element: &Element) -> Result<MetaInfo, MetaInfoError> {
+        let mut ret = MetaInfo::parse_root(element)?;
+        ret.set_info_bytes(encode(&element["info"]));
+        return Ok(ret);
+    }
+
+    /// `try_from_element` without setting `info_bytes`, which the caller has to do.
+    fn parse_root(element: &Element) -> Result<MetaInfo, MetaInfoError> {
+        let hashmap = element
+            .convert_to_ref_dict()
+            .ok_or(MetaInfoError::NotADictionary)?;
+
+        let announce = match hashmap.get(b"announce".as_slice()) {
+            Some(x) => Some(
+                x.convert_to_str()
+                    .ok_or(MetaInfoError::WrongType("announce"))?,
+            ),
+            None => None,
+        };
+
+        let info_dict = typed_field(hashmap, "info", Element::convert_to_ref_dict)?;
+        let meta_version = info_dict
+            .get(b"meta version".as_slice())
+            .and_then(|x| x.convert_to_u64());
+        let file_tree = match meta_version {
+            Some(2) => Some(FileTree::try_from_element(field(info_dict, "file tree")?)?),
+            _ => None,
+        };
+        let mut is_default_name = false;
+        let (name, raw_name) = match name_field(info_dict) {
+            Err(MetaInfoError::MissingField("name")) => {
+                let name = default_name(info_dict, file_tree.as_ref())
+                    .ok_or(MetaInfoError::MissingField("name"))?;
+                let raw_name = name.as_bytes().to_vec();
+                is_default_name = true;
+                (name, raw_name)
+            }
+            x => x?,
+        };
+        let has_length = info_dict.contains_key(b"length".as_slice());
+        if has_length && info_dict.contains_key(b"files".as_slice()) {
+            return Err(MetaInfoError::LengthAndFiles);
+        }
+        let mut info = match (info_dict.get(b"files".as_slice()), &file_tree) {
+            // v2-only torrents have no `pieces`, and their files are described by the file tree alone.
+            (None, Some(tree)) if !info_dict.contains_key(b"pieces".as_slice()) => {
+                let piece_length =
+                    typed_field(info_dict, "piece length", Element::convert_to_usize)?;
+                let common_file_info = CommonFileInfo {
+                    piece_length,
+                    pieces: Vec::<Vec<u8>>::new(),
+                    is_private: private_field(info_dict),
+                    source: source_field(info_dict),
+                };
+                tree.to_file_info(&name, common_file_info)
+            }
+            (Some(files), _) => {
+                let common_file_info = CommonFileInfo::try_from_dict(info_dict)?;
+                let files = files
+                    .convert_to_ref_list()
+                    .ok_or(MetaInfoError::WrongType("files"))?;
+                let mut info =
+                    MultipleFileInfo::try_new_with_common_info(common_file_info, &name, files)?;
+                info.raw_name = raw_name;
+                FileInfo::MultipleFile(info)
+            }
+            (None, _) if !has_length => return Err(MetaInfoError::NoLengthOrFiles),
+            (None, _) => FileInfo::SingleFile(SingleFileInfo::try_new_with_name(
+                CommonFileInfo::try_from_dict(info_dict)?,
+                info_dict,
+                name,
+                raw_name,
             )?),
         };
+        if is_default_name && !strip_default_name(&mut info) {
+            return Err(MetaInfoError::MissingField("name"));
+        }
+        if info.checked_total_size().is_none() {
+            return Err(MetaInfoError::TotalSizeOverflow);
+        }
 
-        let mut ret = MetaInfo::new(info, announce);
-        for key in hashmap.keys() {
-            match key.as_str() {
-                "announce-list" => {
-                    ret.announce_list = hashmap
-                        .get(key)? // this must be Vec<Vec<String>>
-                        .convert_to_ref_list()?
-                        .iter()
-                        .map(|ve| ve.convert_to_string_list())
-                        .collect();
+        let mut ret = MetaInfo::without_info_bytes(info, announce);
+        ret.meta_version = meta_version;
+        ret.file_tree = file_tree;
+        for (key, val) in hashmap {
+            match key.as_slice() {
+                b"announce-list" => {
+                    ret.announce_list = parse_announce_list(val)?;
+                }
+                b"creation date" => {
+                    ret.creation_date = val.convert_to_u64();
+                }
+                b"comment" => {
+                    ret.comment = val.convert_to_string();
                 }
-                "creation date" => {
-                    ret.creation_date = hashmap.get(key)?.convert_to_u64();
+                b"created by" => {
+                    ret.created_by = val.convert_to_string();
                 }
-                "comment" => {
-                    ret.comment = hashmap.get(key)?.convert_to_string();
+                b"encoding" => {
+                    ret.encoding = val.convert_to_string();
                 }
-                "created by" => {
-                    ret.created_by = hashmap.get(key)?.convert_to_string();
+                b"nodes" => {
+                    // DHT nodes are only a hint, so malformed ones are ignored instead of failing.
+                    ret.nodes = val.iter_list().and_then(|nodes| {
+                        nodes
+                            .map(|node| match node.convert_to_ref_list()?.as_slice() {
+                                [host, port] => {
+                                    Some((host.convert_to_string()?, port.convert_to_u16()?))
+                                }
+                                _ => None,
+                            })
+                            .collect()
+                    });
                 }
-                "encoding" => {
-                    ret.encoding = hashmap.get(key)?.convert_to_string();
+                b"url-list" => {
+                    // Both a single URL and a list of URLs are seen in the wild. An empty single URL
+                    // is as good as absent, and empty URLs in a list are dropped.
+                    if let Some(x) = val.convert_to_string() {
+                        ret.url_list = Some(vec![x]).filter(|x| !x[0].is_empty());
+                        ret.url_list_is_single = ret.url_list.is_some();
+                    } else {
+                        ret.url_list = val
+                            .convert_to_string_list()
+                            .map(|x| x.into_iter().filter(|url| !url.is_empty()).collect());
+                    }
+                }
+                b"httpseeds" => {
+                    ret.http_seeds = val.convert_to_string_list();
+                }
+                b"piece layers" => {
+                    ret.piece_layers = Some(parse_piece_layers(val)?);
                 }
                 _ => (),
             }
         }
 
+        return Ok(ret);
+    }
+
+    /// Parse exactly one bencoded torrent and hash the `info` dictionary as it appears in `bencode`.
+    /// `from_element` can only hash the re-encoded dictionary, which differs for non-canonical input.
+    pub fn from_u8(bencode: &[u8]) -> Result<MetaInfo, MetaInfoError> {
+        let (element, spans) = decode_with_spans(bencode)?;
+        let mut ret = MetaInfo::parse_root(&element)?;
+        let info_span = spans
+            .get("info")
+            .ok_or(MetaInfoError::MissingField("info"))?
+            .span();
+        ret.set_info_bytes(bencode[info_span.range()].to_vec());
+        return Ok(ret);
+    }
+
+    /// Same as `from_u8_len_check`, but faster for large torrents.
+    /// Byte strings are borrowed from `bencode` while decoding, and `pieces` and `files`, usually the
+    /// largest part, are read straight from the borrowed elements instead of being copied into an `Element`.
+    pub fn parse_fast(bencode: &[u8]) -> Option<MetaInfo> {
+        // Walk the top-level dictionary by hand to find where `info` lies in `bencode`.
+        let mut rest = bencode.strip_prefix(b"d")?;
+        let mut root = BTreeMap::<Vec<u8>, Element>::new();
+        let mut info = None;
+        while !rest.starts_with(b"e") {
+            let (ElementRef::ByteString(key), after_key) = decode_borrowed_prefix(rest)? else {
+                return None;
+            };
+            let (val, after_val) = decode_borrowed_prefix(after_key)?;
+            if key == b"info" {
+                if info.is_some() {
+                    return None;
+                }
+                info = Some((val, &after_key[..after_key.len() - after_val.len()]));
+            } else if root.insert(key.to_vec(), val.to_owned()).is_some() {
+                return None;
+            }
+            rest = after_val;
+        }
+        if rest != b"e" {
+            return None;
+        }
+
+        let (info, info_bytes) = info?;
+        let mut pieces = None;
+        let mut files = None;
+        if let ElementRef::Dictionary(dict) = &info {
+            if let Some(ElementRef::ByteString(x)) = dict.get(b"pieces".as_slice()) {
+                pieces = Some(*x);
+            }
+            // Without a name, `parse_root` takes the default name from the files.
+            let has_name = dict.contains_key(b"name".as_slice())
+                || dict.contains_key(b"name.utf-8".as_slice());
+            if let (Some(ElementRef::List(x)), true) = (dict.get(b"files".as_slice()), has_name) {
+                files = Some(x);
+            }
+        }
+        let info = match &info {
+            // Leave `pieces` and `files` empty for `parse_root`, and fill them after that. `parse_root` then
+            // skips its checks on them, so every one is repeated below: the length of `pieces`, the
+            // entries of `files` by the same `MultipleFileInfoFile::parse`, and the total size.
+            ElementRef::Dictionary(dict) => Element::Dictionary(
+                dict.iter()
+                    .map(|(key, val)| match *key {
+                        b"pieces" if pieces.is_some() => {
+                            (key.to_vec(), Element::ByteString(Vec::new()))
+                        }
+                        b"files" if files.is_some() => (key.to_vec(), Element::List(Vec::new())),
+                        _ => (key.to_vec(), val.to_owned()),
+                    })
+                    .collect(),
+            ),
+            x => x.to_owned(),
+        };
+        root.insert(b"info".to_vec(), info);
+
+        let mut ret = MetaInfo::parse_root(&Element::Dictionary(root)).ok()?;
+        if let Some(pieces) = pieces {
+            if !pieces.len().is_multiple_of(CommonFileInfo::PIECE_HASH_SIZE) {
+                return None;
+            }
+            ret.info.common_file_info_mut().pieces = pieces
+                .chunks(CommonFileInfo::PIECE_HASH_SIZE)
+                .map(<[u8]>::to_vec)
+                .collect();
+        }
+        if let (Some(files), FileInfo::MultipleFile(info)) = (files, &mut ret.info) {
+            info.files = files
+                .iter()
+                .map(|file| MultipleFileInfoFile::parse(file.dict()?).ok())
+                .collect::<Option<_>>()?;
+        }
+        ret.info.checked_total_size()?;
+        ret.set_info_bytes(info_bytes.to_vec());
         return Some(ret);
     }
 
+    /// Same as `from_u8`, but without telling why parsing failed.
     pub fn from_u8_len_check(bencode: &[u8]) -> Option<MetaInfo> {
-        let element = decode_len_check(bencode)?;
-        return MetaInfo::from_element(&element);
+        return MetaInfo::from_u8(bencode).ok();
     }
 
+    /// Same as `from_u8_len_check`, but ignore bytes after the torrent.
     #[allow(dead_code)]
     pub fn from_u8_no_len_check(bencode: &[u8]) -> Option<MetaInfo> {
-        let element = decode_no_len_check(bencode)?;
-        return MetaInfo::from_element(&element);
+        let (_, len) = decode_with_consumed(bencode)?;
+        return MetaInfo::from_u8(&bencode[..len]).ok();
+    }
+
+    /// Store `info_bytes` with its hashes. `file_tree` must already be set to get the v2 hash.
+    fn set_info_bytes(&mut self, info_bytes: Vec<u8>) {
+        self.info_hash = Sha1::digest(&info_bytes).into();
+        self.info_hash_v2 = self
+            .file_tree
+            .as_ref()
+            .map(|_| Sha256::digest(&info_bytes).into());
+        self.info_bytes = info_bytes;
+    }
+
+    /// Encode the torrent back to a `.torrent` file.
+    /// `info` is written byte for byte as it was parsed, so `info_hash` stays the same,
+    /// while the other top-level keys reflect the current fields and unknown ones are dropped.
+    pub fn to_bencode(&self) -> Vec<u8> {
+        let mut dict = BTreeMap::<Vec<u8>, Element>::new();
+        if let Some(x) = &self.announce {
+            dict.insert(b"announce".to_vec(), Element::from(x.as_str()));
+        }
+        if let Some(x) = &self.announce_list {
+            let tiers = x
+                .iter()
+                .map(|tier| Element::List(tier.iter().map(|x| Element::from(x.as_str())).collect()))
+                .collect();
+            dict.insert(b"announce-list".to_vec(), Element::List(tiers));
+        }
+        if let Some(x) = self.creation_date {
+            dict.insert(b"creation date".to_vec(), Element::from_i128(x as i128));
+        }
+        if let Some(x) = &self.comment {
+            dict.insert(b"comment".to_vec(), Element::from(x.as_str()));
+        }
+        if let Some(x) = &self.created_by {
+            dict.insert(b"created by".to_vec(), Element::from(x.as_str()));
+        }
+        if let Some(x) = &self.encoding {
+            dict.insert(b"encoding".to_vec(), Element::from(x.as_str()));
+        }
+        dict.insert(b"info".to_vec(), Element::Raw(self.info_bytes.clone()));
+        if let Some(x) = &self.nodes {
+            let nodes = x
+                .iter()
+                .map(|(host, port)| {
+                    Element::List(vec![
+                        Element::from(host.as_str()),
+                        Element::from(*port as i64),
+                    ])
+                })
+                .collect();
+            dict.insert(b"nodes".to_vec(), Element::List(nodes));
+        }
+        if let Some(x) = &self.url_list {
+            let urls = match x.as_slice() {
+                [url] if self.url_list_is_single => Element::from(url.as_str()),
+                _ => Element::List(x.iter().map(|x| Element::from(x.as_str())).collect()),
+            };
+            dict.insert(b"url-list".to_vec(), urls);
+        }
+        if let Some(x) = &self.http_seeds {
+            let urls = x.iter().map(|x| Element::from(x.as_str())).collect();
+            dict.insert(b"httpseeds".to_vec(), Element::List(urls));
+        }
+        if let Some(x) = &self.piece_layers {
+            let layers = x
+                .iter()
+                .map(|(root, hashes)| (root.to_vec(), Element::from(hashes.concat())))
+                .collect::<BTreeMap<Vec<u8>, Element>>();
+            dict.insert(b"piece layers".to_vec(), Element::Dictionary(layers));
+        }
+        return encode(&Element::Dictionary(dict));
+    }
+
+    /// Write `to_bencode` to `path`, replacing the file if it exists.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        return std::fs::write(path, self.to_bencode());
+    }
+
+    /// Bencoded `info` dictionary, which is the metadata exchanged with peers (BEP 9).
+    /// These are the original bytes of a parsed torrent, so they hash to `info_hash` even if they are
+    /// not canonical, and the canonical encoding for a torrent built with `new`.
+    pub fn info_bytes(&self) -> &[u8] {
+        return &self.info_bytes;
+    }
+
+    /// Size of `info_bytes`, sent as `metadata_size` in the extension handshake (BEP 9).
+    pub fn metadata_size(&self) -> usize {
+        return self.info_bytes.len();
+    }
+
+    /// `info_bytes` split into 16 KiB pieces to serve with `ut_metadata` (BEP 9). The last one is shorter
+    /// unless the size is a multiple of 16 KiB. A peer reassembling them must check the SHA-1 of the result
+    /// against `info_hash` before trusting it.
+    pub fn metadata_pieces(&self) -> Vec<&[u8]> {
+        return self.info_bytes.chunks(Self::METADATA_PIECE_SIZE).collect();
+    }
+
+    /// SHA-1 of the bencoded `info` dictionary, which identifies the torrent to trackers and peers.
+    /// This is the v1 info hash. A v2-only torrent has no v1 swarm, so use `info_hash_v2` for it.
+    pub fn info_hash(&self) -> InfoHash {
+        return InfoHash(self.info_hash);
+    }
+
+    /// SHA-256 of the bencoded `info` dictionary, which identifies the v2 swarm (BEP 52).
+    /// `None` for v1 torrents.
+    pub fn info_hash_v2(&self) -> Option<[u8; 32]> {
+        return self.info_hash_v2;
+    }
+
+    /// Whether the torrent has v1 `pieces`, so that it can join a v1 swarm.
+    pub fn is_v1(&self) -> bool {
+        return !self.is_v2() || !self.info.common_file_info().pieces.is_empty();
+    }
+
+    /// Whether the torrent has a v2 `file tree`, so that it can join a v2 swarm.
+    pub fn is_v2(&self) -> bool {
+        return self.file_tree.is_some();
+    }
+
+    /// Whether the torrent can join both the v1 and the v2 swarm.
+    pub fn is_hybrid(&self) -> bool {
+        return self.is_v1() && self.is_v2();
+    }
+
+    /// `info_hash` as 40 lowercase hex characters.
+    pub fn info_hash_hex(&self) -> String {
+        return self.info_hash().to_hex();
+    }
+
+    /// `info_hash` as 32 base32 characters, the other form accepted in `magnet:?xt=urn:btih:`.
+    pub fn info_hash_base32(&self) -> String {
+        return self.info_hash().to_base32();
+    }
+
+    /// Magnet link `magnet:?xt=urn:btih:<info_hash>&dn=<name>&tr=<tracker>...`.
+    /// A v2 torrent gets `xt=urn:btmh:1220<info_hash_v2>`, the SHA-256 multihash of BEP 52, instead,
+    /// and a hybrid torrent gets both.
+    /// `tr` lists `announce` followed by every tracker in the announce-list, each only once.
+    /// A missing or empty `announce` is left out, so a torrent without trackers still gets a valid `xt` link.
+    pub fn to_magnet(&self) -> String {
+        let name = self.name();
+        let mut xt = Vec::<String>::new();
+        if self.is_v1() {
+            xt.push(format!("urn:btih:{}", self.info_hash_hex()));
+        }
+        if let Some(x) = self.info_hash_v2 {
+            xt.push(format!("urn:btmh:1220{}", hex_encode(&x)));
+        }
+        let mut magnet = format!("magnet:?xt={}", xt.join("&xt="));
+        if !name.is_empty() {
+            magnet.push_str(&format!("&dn={}", url_encode(name.as_bytes())));
+        }
+
+        let mut trackers = Vec::<&str>::new();
+        if let Some(x) = self.announce.as_deref().filter(|x| !x.is_empty()) {
+            trackers.push(x);
+        }
+        for tier in self.announce_list.iter().flatten() {
+            for tracker in tier {
+                if !tracker.is_empty() && !trackers.contains(&tracker.as_str()) {
+                    trackers.push(tracker);
+                }
+            }
+        }
+        for tracker in trackers {
+            magnet.push_str(&format!("&tr={}", url_encode(tracker.as_bytes())));
+        }
+        return magnet;
+    }
+
+    /// Parse a magnet link made by `to_magnet` or any other client.
+    /// The info hash comes from the first `xt=urn:btih:`, as 40 hex or 32 base32 characters.
+    /// Return `None` if there is none, or if a known parameter is not valid percent-encoded UTF-8.
+    pub fn from_magnet(uri: &str) -> Option<PartialMetaInfo> {
+        let query = uri.strip_prefix("magnet:?")?;
+        let mut info_hash = None;
+        let mut ret = PartialMetaInfo::default();
+        for param in query.split('&') {
+            let (key, val) = param.split_once('=').unwrap_or((param, ""));
+            match key {
+                "xt" if info_hash.is_none() => {
+                    let Some(hash) = val.strip_prefix("urn:btih:") else {
+                        continue;
+                    };
+                    info_hash = Some(hash.parse::<InfoHash>().ok()?);
+                }
+                "dn" => {
+                    ret.name = Some(String::from_utf8(url_decode(val)?).ok()?);
+                }
+                "tr" => {
+                    ret.trackers.push(String::from_utf8(url_decode(val)?).ok()?);
+                }
+                _ => (),
+            }
+        }
+        ret.info_hash = info_hash?;
+        return Some(ret);
+    }
+
+    /// Complete a magnet link with the bencoded `info` dictionary fetched from peers by `ut_metadata` (BEP 9).
+    /// Return `None` if the SHA-1 of `info` is not `magnet.info_hash` or `info` is not a valid info dictionary.
+    /// The first tracker of the magnet becomes `announce`, and all of them form a single tier of the announce-list.
+    /// `dn` is only used when `info` has no `name`, since the name in `info` is covered by the hash.
+    pub fn from_info_bytes(info: &[u8], magnet: &PartialMetaInfo) -> Option<MetaInfo> {
+        if InfoHash(Sha1::digest(info).into()) != magnet.info_hash {
+            return None;
+        }
+        let mut info_element = decode(info).ok()?;
+        if let (Element::Dictionary(dict), Some(name)) = (&mut info_element, &magnet.name) {
+            dict.entry(b"name".to_vec())
+                .or_insert_with(|| Element::ByteString(name.as_bytes().to_vec()));
+        }
+        let root = Element::Dictionary(BTreeMap::from([(b"info".to_vec(), info_element)]));
+
+        let mut ret = MetaInfo::try_from_element(&root).ok()?;
+        ret.set_info_bytes(info.to_vec());
+        ret.announce = magnet.trackers.first().cloned();
+        if magnet.trackers.len() > 1 {
+            ret.announce_list = Some(vec![magnet.trackers.clone()]);
+        }
+        return Some(ret);
+    }
+
+    /// Parse bencoded torrent like `from_u8_len_check` and reject it if it fails checks enabled in `options`.
+    pub fn from_u8_with_options(bencode: &[u8], options: &ParseOptions) -> Option<MetaInfo> {
+        let mut ret = MetaInfo::from_u8_len_check(bencode)?;
+        if options.reject_path_collisions && ret.find_path_collision(options.case_insensitive_paths)
+        {
+            return None;
+        }
+        if options.dedup_announce_list {
+            ret.dedup_announce_list();
+        }
+        return Some(ret);
+    }
+}
+
+#[allow(dead_code)]
+impl MetaInfo {
+    const MIN_USUAL_PIECE_LENGTH: usize = 16 * 1024;
+    const MAX_USUAL_PIECE_LENGTH: usize = 32 * 1024 * 1024;
+    /// Bytes read from disk at a time by `verified_bitfield`.
+    const READ_CHUNK_SIZE: usize = 64 * 1024;
+    /// Size of a metadata piece of `ut_metadata` (BEP 9).
+    const METADATA_PIECE_SIZE: usize = 16 * 1024;
+
+    fn common_file_info(&self) -> &CommonFileInfo {
+        return self.info.common_file_info();
+    }
+
+    /// Non-fatal notes about an unusual `piece length`, for torrent inspection.
+    /// An empty vector means the piece length looks fine.
+    pub fn piece_length_warnings(&self) -> Vec<&'static str> {
+        let piece_length = self.common_file_info().piece_length;
+        let mut warnings = Vec::<&'static str>::new();
+        if piece_length == 0 {
+            warnings.push("piece length is zero");
+            return warnings;
+        }
+
+        if !piece_length.is_power_of_two() {
+            warnings.push("piece length is not a power of two");
+        }
+        if piece_length < Self::MIN_USUAL_PIECE_LENGTH {
+            warnings.push("piece length below 16 KiB may hurt performance");
+        }
+        if piece_length > Self::MAX_USUAL_PIECE_LENGTH {
+            warnings.push("piece length above 32 MiB may hurt performance");
+        }
+        return warnings;
+    }
+
+    /// Check whether two files of multi-file torrent have an identical path.
+    /// Those files would overwrite each other on disk.
+    pub fn has_path_collisions(&self) -> bool {
+        return self.find_path_collision(false);
+    }
+
+    /// Same as `has_path_collisions`, but paths differing only in case also collide.
+    pub fn has_case_insensitive_path_collisions(&self) -> bool {
+        return self.find_path_collision(true);
+    }
+
+    fn find_path_collision(&self, case_insensitive: bool) -> bool {
+        let files = match &self.info {
+            FileInfo::MultipleFile(x) => &x.files,
+            FileInfo::SingleFile(_) => return false,
+        };
+
+        let mut paths = HashSet::<String>::with_capacity(files.len());
+        // Padding files are never saved, and those of the same size usually share a path like `.pad/N`.
+        for file in files.iter().filter(|file| !file.is_padding()) {
+            let path = file.path.join("/");
+            let path = if case_insensitive {
+                path.to_lowercase()
+            } else {
+                path
+            };
+            if !paths.insert(path) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    /// Files of the content in order of the concatenated stream with their lengths and whether they
+    /// are padding files (BEP 47), which are zeros and have no path.
+    /// The content of torrent is `root/name` for single-file and `root/name/path...` for multi-file.
+    /// The path is `None` when `name` or `path` could escape `root` (see `safe_path`).
+    fn content_files(&self, root: &Path) -> Vec<(Option<PathBuf>, usize, bool)> {
+        let base = Some(root.join(self.name())).filter(|_| is_safe_component(self.name()));
+        match &self.info {
+            FileInfo::SingleFile(x) => vec![(base, x.length, false)],
+            FileInfo::MultipleFile(x) => x
+                .files
+                .iter()
+                .map(|file| {
+                    let path = base
+                        .as_ref()
+                        .filter(|_| !file.is_padding())
+                        .and_then(|base| file.safe_path(base));
+                    (path, file.length, file.is_padding())
+                })
+                .collect(),
+        }
+    }
+
+    /// Create a torrent of the file or directory at `path`, hashing its content in `piece_length` pieces.
+    /// A directory gives a multi-file torrent of every file under it, sorted by path, where pieces
+    /// run across file boundaries. Symbolic links under a directory are skipped.
+    ///
+    /// # Errors
+    ///
+    /// `InvalidInput` if `piece_length` is zero, if `path` has no UTF-8 file name (e.g. `..`)
+    /// or if a directory has no files, and `InvalidData` on a non-UTF-8 path under the directory.
+    pub fn create_from_path<P: AsRef<Path>>(
+        path: P,
+        piece_length: usize,
+        announce: Option<&str>,
+    ) -> io::Result<MetaInfo> {
+        let invalid_input = |msg: &str| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let path = path.as_ref();
+        if piece_length == 0 {
+            return Err(invalid_input("piece length is zero"));
+        }
+        let name = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .ok_or_else(|| invalid_input("path has no UTF-8 file name"))?;
+        let is_dir = std::fs::metadata(path)?.is_dir();
+        let mut files = Vec::<(Vec<String>, PathBuf)>::new();
+        if is_dir {
+            MetaInfo::collect_dir_files(path, &mut Vec::<String>::new(), &mut files)?;
+            if files.is_empty() {
+                return Err(invalid_input("directory has no files"));
+            }
+        } else {
+            files.push((Vec::<String>::new(), path.to_path_buf()));
+        }
+
+        let mut pieces = Vec::<u8>::new();
+        let mut piece = Vec::<u8>::with_capacity(piece_length);
+        let mut lengths = Vec::<usize>::with_capacity(files.len());
+        for (_, file_path) in &files {
+            let mut file = File::open(file_path)?;
+            let mut length = 0;
+            loop {
+                let start = piece.len();
+                piece.resize(piece_length, 0);
+                let n = match file.read(&mut piece[start..]) {
+                    Ok(x) => x,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                        piece.truncate(start);
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                };
+                piece.truncate(start + n);
+                if n == 0 {
+                    break;
+                }
+                length += n;
+                if piece.len() == piece_length {
+                    pieces.extend_from_slice(&Sha1::digest(&piece));
+                    piece.clear();
+                }
+            }
+            lengths.push(length);
+        }
+        if !piece.is_empty() {
+            pieces.extend_from_slice(&Sha1::digest(&piece));
+        }
+
+        let common_file_info = CommonFileInfo::new(piece_length, &pieces, false)
+            .expect("pieces are made of 20-byte hashes");
+        let info = if is_dir {
+            let files = files
+                .into_iter()
+                .zip(lengths)
+                .map(|((path, _), length)| MultipleFileInfoFile::new(length, path, None))
+                .collect();
+            FileInfo::MultipleFile(MultipleFileInfo::new(common_file_info, name, files))
+        } else {
+            FileInfo::SingleFile(SingleFileInfo::new(
+                common_file_info,
+                name,
+                lengths[0],
+                None,
+            ))
+        };
+        return Ok(MetaInfo::new(info, announce));
+    }
+
+    /// Append the files under `dir` to `ret` with their path components after `prefix`, sorted by name.
+    fn collect_dir_files(
+        dir: &Path,
+        prefix: &mut Vec<String>,
+        ret: &mut Vec<(Vec<String>, PathBuf)>,
+    ) -> io::Result<()> {
+        let mut entries = std::fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|x| x.file_name());
+        for entry in entries {
+            let file_type = entry.file_type()?;
+            if file_type.is_symlink() {
+                continue;
+            }
+            let name = entry.file_name().into_string().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "path is not valid UTF-8")
+            })?;
+            prefix.push(name);
+            if file_type.is_dir() {
+                MetaInfo::collect_dir_files(&entry.path(), prefix, ret)?;
+            } else {
+                ret.push((prefix.clone(), entry.path()));
+            }
+            prefix.pop();
+        }
+        return Ok(());
+    }
+
+    /// Check pieces of the content under `root` against their hashes.
+    /// Pieces whose data is missing (absent or short files) or does not match are left unset,
+    /// which is what a client needs to resume a partial download.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - directory where the content is downloaded. See `content_files` for the layout.
+    ///   Files with unsafe paths are never read and count as missing.
+    pub fn verified_bitfield<P: AsRef<Path>>(&self, root: P) -> io::Result<PieceBitfield> {
+        let common_file_info = self.common_file_info();
+        let piece_length = common_file_info.piece_length;
+        let pieces = &common_file_info.pieces;
+        let mut bitfield = PieceBitfield::new(pieces.len());
+        if piece_length == 0 {
+            return Ok(bitfield);
+        }
+
+        // `piece length` comes from the torrent, so pieces are hashed a chunk at a time instead of being
+        // buffered whole, which would let a huge `piece length` abort the process on allocation.
+        let mut chunk = vec![0u8; piece_length.min(Self::READ_CHUNK_SIZE)];
+        let mut hasher = Sha1::new();
+        let mut piece_len = 0;
+        let mut is_piece_complete = true;
+        let mut index = 0;
+        let mut finish_piece =
+            |hasher: &mut Sha1, piece_len: &mut usize, is_piece_complete: &mut bool| {
+                let digest = hasher.finalize_reset();
+                if *is_piece_complete
+                    && index < pieces.len()
+                    && digest.as_slice() == pieces[index].as_slice()
+                {
+                    bitfield.set(index);
+                }
+                index += 1;
+                *piece_len = 0;
+                *is_piece_complete = true;
+            };
+
+        for (path, length, is_padding) in self.content_files(root.as_ref()) {
+            let mut file = match path.map(File::open) {
+                Some(Ok(x)) => Some(x),
+                Some(Err(e)) if e.kind() == io::ErrorKind::NotFound => None,
+                Some(Err(e)) => return Err(e),
+                None => None,
+            };
+
+            let mut remaining = length;
+            while remaining > 0 {
+                let chunk_len = remaining.min(piece_length - piece_len).min(chunk.len());
+                let chunk = &mut chunk[..chunk_len];
+                chunk.fill(0);
+                if let Some(f) = &mut file {
+                    match f.read_exact(chunk) {
+                        Ok(()) => (),
+                        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                            file = None;
+                            is_piece_complete = false;
+                        }
+                        Err(e) => return Err(e),
+                    }
+                } else if !is_padding {
+                    is_piece_complete = false;
+                }
+                if is_piece_complete {
+                    hasher.update(&*chunk);
+                }
+
+                remaining -= chunk_len;
+                piece_len += chunk_len;
+                if piece_len == piece_length {
+                    finish_piece(&mut hasher, &mut piece_len, &mut is_piece_complete);
+                }
+            }
+        }
+        if piece_len > 0 {
+            finish_piece(&mut hasher, &mut piece_len, &mut is_piece_complete);
+        }
+
+        return Ok(bitfield);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Fresh directory under the system temp directory, removed on drop so that a failing test does
+    /// not leave it behind.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path =
+                std::env::temp_dir().join(format!("rustorrent-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            return TempDir(path);
+        }
+
+        fn path(&self) -> &Path {
+            return &self.0;
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn multiple_file_torrent(paths: &[&str]) -> Vec<u8> {
+        let files: String = paths
+            .iter()
+            .map(|path| format!("d6:lengthi1e4:pathl{}:{}ee", path.len(), path))
+            .collect();
+        format!(
+            "d8:announce9:localhost4:infod5:filesl{}e4:name3:dir12:piece lengthi16384e6:pieces20:{}ee",
+            files,
+            "a".repeat(20)
+        )
+        .into_bytes()
+    }
+
+    /// Multi-file `info` named `a` whose files are named by their index.
+    fn multiple_file_info(lengths: &[usize], piece_length: usize, piece_count: usize) -> FileInfo {
+        let files = lengths
+            .iter()
+            .enumerate()
+            .map(|(i, length)| MultipleFileInfoFile::new(*length, vec![i.to_string()], None))
+            .collect();
+        let pieces = vec![b'a'; piece_count * 20];
+        let common_file_info = CommonFileInfo::new(piece_length, &pieces, false).unwrap();
+        return FileInfo::MultipleFile(MultipleFileInfo::new(common_file_info, "a", files));
+    }
+
+    /// Bencoded single-file torrent `a` with `piece_count` dummy hashes. `info_extra` and `extra`
+    /// are bencoded keys appended to `info` and to the root, so they must sort after `pieces` and
+    /// `info`.
+    fn single_file_torrent(
+        length: u64,
+        piece_length: usize,
+        piece_count: usize,
+        info_extra: &str,
+        extra: &str,
+    ) -> Vec<u8> {
+        return format!(
+            "d4:infod6:lengthi{}e4:name1:a12:piece lengthi{}e6:pieces{}:{}{}e{}e",
+            length,
+            piece_length,
+            piece_count * 20,
+            "a".repeat(piece_count * 20),
+            info_extra,
+            extra
+        )
+        .into_bytes();
+    }
+
+    fn single_file_info(name: &str, length: usize, piece_length: usize, pieces: &[u8]) -> FileInfo {
+        let common_file_info = CommonFileInfo::new(piece_length, pieces, false).unwrap();
+        return FileInfo::SingleFile(SingleFileInfo::new(common_file_info, name, length, None));
+    }
+
+    mod piece_length_warnings_test {
+        use super::*;
+
+        fn helper(piece_length: usize, expect: &[&str]) {
+            let meta_info =
+                MetaInfo::from_u8_len_check(&single_file_torrent(1, piece_length, 1, "", ""))
+                    .unwrap();
+            assert_eq!(meta_info.piece_length_warnings(), expect);
+        }
+
+        #[test]
+        fn piece_length_warnings_01() {
+            helper(256 * 1024, &[]);
+        }
+
+        #[test]
+        fn piece_length_warnings_02() {
+            helper(
+                17,
+                &[
+                    "piece length is not a power of two",
+                    "piece length below 16 KiB may hurt performance",
+                ],
+            );
+        }
+
+        #[test]
+        fn piece_length_warnings_03() {
+            helper(
+                64 * 1024 * 1024,
+                &["piece length above 32 MiB may hurt performance"],
+            );
+        }
+
+        #[test]
+        fn piece_length_warnings_04() {
+            helper(0, &["piece length is zero"]);
+        }
+    }
+
+    mod path_collision_test {
+        use super::*;
+
+        fn helper(paths: &[&str], expect: bool, expect_case_insensitive: bool) {
+            let meta_info = MetaInfo::from_u8_len_check(&multiple_file_torrent(paths)).unwrap();
+            assert_eq!(meta_info.has_path_collisions(), expect);
+            assert_eq!(
+                meta_info.has_case_insensitive_path_collisions(),
+                expect_case_insensitive
+            );
+        }
+
+        #[test]
+        fn path_collision_01() {
+            helper(&["a", "b"], false, false);
+        }
+
+        #[test]
+        fn path_collision_02() {
+            helper(&["a", "b", "a"], true, true);
+        }
+
+        #[test]
+        fn path_collision_03() {
+            helper(&["readme", "README"], false, true);
+        }
+
+        #[test]
+        fn path_collision_04() {
+            let torrent = multiple_file_torrent(&["readme", "README"]);
+            let mut options = ParseOptions {
+                reject_path_collisions: true,
+                case_insensitive_paths: false,
+                ..Default::default()
+            };
+            assert!(MetaInfo::from_u8_with_options(&torrent, &options).is_some());
+            options.case_insensitive_paths = true;
+            assert!(MetaInfo::from_u8_with_options(&torrent, &options).is_none());
+        }
+
+        #[test]
+        fn path_collision_05() {
+            let torrent = multiple_file_torrent(&["a", "a"]);
+            assert!(MetaInfo::from_u8_with_options(&torrent, &ParseOptions::default()).is_some());
+            let options = ParseOptions {
+                reject_path_collisions: true,
+                ..Default::default()
+            };
+            assert!(MetaInfo::from_u8_with_options(&torrent, &options).is_none());
+        }
+    }
+
+    mod verified_bitfield_test {
+        use super::*;
+        use std::fs;
+
+        fn piece_hashes(content: &[u8], piece_length: usize) -> Vec<u8> {
+            content
+                .chunks(piece_length)
+                .flat_map(|chk| Sha1::digest(chk).to_vec())
+                .collect()
+        }
+
+        fn torrent(files: &[(&str, &[u8])], piece_length: usize) -> MetaInfo {
+            let content: Vec<u8> = files.iter().flat_map(|(_, data)| data.to_vec()).collect();
+            let files: String = files
+                .iter()
+                .map(|(path, data)| {
+                    format!("d6:lengthi{}e4:pathl{}:{}ee", data.len(), path.len(), path)
+                })
+                .collect();
+            let mut bencode = format!(
+                "d8:announce9:localhost4:infod5:filesl{}e4:name3:dir12:piece lengthi{}e6:pieces",
+                files, piece_length
+            )
+            .into_bytes();
+            let pieces = piece_hashes(&content, piece_length);
+            bencode.extend_from_slice(format!("{}:", pieces.len()).as_bytes());
+            bencode.extend_from_slice(&pieces);
+            bencode.extend_from_slice(b"ee");
+            MetaInfo::from_u8_len_check(&bencode).unwrap()
+        }
+
+        #[test]
+        fn verified_bitfield_01() {
+            let dir = TempDir::new("verified-bitfield");
+            let root = dir.path();
+            fs::create_dir_all(root.join("dir")).unwrap();
+            // piece 0: a[0..4], piece 1: a[4..6] + b[0..2], piece 2: b[2..6], piece 3: c, piece 4: d
+            let meta_info = torrent(
+                &[
+                    ("a", b"012345"),
+                    ("b", b"abcdef"),
+                    ("c", b"ABCD"),
+                    ("d", b"xy"),
+                ],
+                4,
+            );
+            fs::write(root.join("dir/a"), b"012345").unwrap();
+            fs::write(root.join("dir/c"), b"ABCD").unwrap();
+            fs::write(root.join("dir/d"), b"xz").unwrap();
+
+            let bitfield = meta_info.verified_bitfield(root).unwrap();
+
+            assert_eq!(bitfield.len(), 5);
+            assert!(bitfield.has(0));
+            assert!(!bitfield.has(1));
+            assert!(!bitfield.has(2));
+            assert!(bitfield.has(3));
+            assert!(!bitfield.has(4));
+        }
+
+        #[test]
+        fn verified_bitfield_02() {
+            let dir = TempDir::new("verified-bitfield-missing");
+            let root = dir.path();
+            let meta_info = torrent(&[("a", b"012345")], 4);
+            let bitfield = meta_info.verified_bitfield(root).unwrap();
+            assert!(!bitfield.has(0));
+            assert!(!bitfield.has(1));
+        }
+
+        #[test]
+        fn verified_bitfield_03() {
+            let dir = TempDir::new("verified-bitfield-traversal");
+            let root = dir.path();
+            fs::create_dir_all(root.join("dir")).unwrap();
+            fs::write(root.join("secret"), b"0123").unwrap();
+            // `dir/../secret` matches the hash, but must not be read from outside `dir`.
+            let meta_info = torrent(&[("../secret", b"0123")], 4);
+            let bitfield = meta_info.verified_bitfield(root).unwrap();
+            assert!(!bitfield.has(0));
+        }
+
+        #[test]
+        fn verified_bitfield_04() {
+            let dir = TempDir::new("verified-bitfield-empty");
+            let root = dir.path();
+            fs::create_dir_all(root.join("dir")).unwrap();
+            // The empty file `b` does not exist on disk, which is as good as being downloaded.
+            let meta_info = torrent(&[("a", b"012345"), ("b", b""), ("c", b"ab")], 4);
+            fs::write(root.join("dir/a"), b"012345").unwrap();
+            fs::write(root.join("dir/c"), b"ab").unwrap();
+            let bitfield = meta_info.verified_bitfield(root).unwrap();
+            assert!(bitfield.has(0));
+            assert!(bitfield.has(1));
+        }
+
+        #[test]
+        fn verified_bitfield_05() {
+            let dir = TempDir::new("verified-bitfield-padding");
+            let root = dir.path();
+            fs::create_dir_all(root.join("dir")).unwrap();
+            // The padding file is zeros and is never looked for on disk, even if something is there.
+            let mut bencode = b"d4:infod5:filesld6:lengthi3e4:pathl1:aeed4:attr1:p6:lengthi1e4:pathl4:.pad1:1eed6:lengthi2e4:pathl1:beee4:name3:dir12:piece lengthi4e6:pieces40:".to_vec();
+            bencode.extend_from_slice(&piece_hashes(b"abc\0de", 4));
+            bencode.extend_from_slice(b"ee");
+            let meta_info = MetaInfo::from_u8(&bencode).unwrap();
+            fs::write(root.join("dir/a"), b"abc").unwrap();
+            fs::write(root.join("dir/b"), b"de").unwrap();
+            fs::create_dir_all(root.join("dir/.pad")).unwrap();
+            fs::write(root.join("dir/.pad/1"), b"x").unwrap();
+            let bitfield = meta_info.verified_bitfield(root).unwrap();
+            assert!(bitfield.has(0));
+            assert!(bitfield.has(1));
+        }
+
+        #[test]
+        fn verified_bitfield_06() {
+            let dir = TempDir::new("verified-bitfield-huge");
+            let root = dir.path();
+            // A `piece length` from the torrent far above the content must not be allocated up front.
+            // The content spans several chunks read from disk.
+            let content: Vec<u8> = (0..200_000).map(|x| (x % 251) as u8).collect();
+            let mut bencode = format!(
+                "d4:infod6:lengthi{}e4:name1:a12:piece lengthi{}e6:pieces20:",
+                content.len(),
+                i64::MAX
+            )
+            .into_bytes();
+            bencode.extend_from_slice(&Sha1::digest(&content));
+            bencode.extend_from_slice(b"ee");
+            let meta_info = MetaInfo::from_u8(&bencode).unwrap();
+            fs::write(root.join("a"), &content).unwrap();
+            assert!(meta_info.verified_bitfield(root).unwrap().has(0));
+            fs::write(root.join("a"), &content[1..]).unwrap();
+            assert!(!meta_info.verified_bitfield(root).unwrap().has(0));
+        }
+    }
+
+    /// Sample torrents under `tests/fixtures`, which are all canonically bencoded.
+    pub(crate) mod fixture_test {
+        use super::*;
+        use std::fs;
+        use std::path::PathBuf;
+
+        /// Bytes of the sample torrent `name` under `tests/fixtures`.
+        pub(crate) fn fixture(name: &str) -> Vec<u8> {
+            return fs::read(
+                PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/fixtures")
+                    .join(name),
+            )
+            .unwrap();
+        }
+
+        fn fixtures() -> Vec<(PathBuf, Vec<u8>)> {
+            let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+            let mut ret: Vec<(PathBuf, Vec<u8>)> = fs::read_dir(dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "torrent"))
+                .map(|path| {
+                    let bencode = fs::read(&path).unwrap();
+                    (path, bencode)
+                })
+                .collect();
+            ret.sort();
+            return ret;
+        }
+
+        #[test]
+        fn fixture_01() {
+            assert!(fixtures().len() >= 7);
+        }
+
+        #[test]
+        fn fixture_02() {
+            for (path, bencode) in fixtures() {
+                let meta_info = MetaInfo::from_u8_len_check(&bencode)
+                    .unwrap_or_else(|| panic!("{} does not parse", path.display()));
+                assert!(meta_info.total_size() > 0, "{}", path.display());
+                if let FileInfo::MultipleFile(x) = &meta_info.info {
+                    assert!(!x.files.is_empty(), "{}", path.display());
+                }
+            }
+        }
+
+        #[test]
+        fn fixture_03() {
+            for (path, bencode) in fixtures() {
+                let element = decode_len_check(&bencode).unwrap();
+                assert_eq!(encode(&element), bencode, "{}", path.display());
+            }
+        }
+
+        #[test]
+        fn fixture_04() {
+            // Editing a key other than `info` through raw passthrough must keep `info` bytes (and its hash).
+            for (path, bencode) in fixtures() {
+                let mut dict = decode_len_check(&bencode).unwrap().into_dict().unwrap();
+                let info = encode(&dict[b"info".as_slice()]);
+                dict.insert(b"info".to_vec(), Element::Raw(info.clone()));
+                dict.insert(b"comment".to_vec(), Element::ByteString(b"edited".to_vec()));
+
+                let edited = encode(&Element::Dictionary(dict));
+                let edited_dict = decode_len_check(&edited).unwrap().into_dict().unwrap();
+                assert_eq!(
+                    encode(&edited_dict[b"info".as_slice()]),
+                    info,
+                    "{}",
+                    path.display()
+                );
+                assert_eq!(
+                    MetaInfo::from_u8_len_check(&edited).unwrap().info_hash(),
+                    MetaInfo::from_u8_len_check(&bencode).unwrap().info_hash(),
+                    "{}",
+                    path.display()
+                );
+            }
+        }
+
+        #[test]
+        fn fixture_05() {
+            // Computed independently with Python's hashlib over the `info` bytes of each file.
+            let expected = [
+                ("hybrid.torrent", "ec867b5cc8e39d04b59c3f449d2bd74cca0da9fa"),
+                (
+                    "multi_file.torrent",
+                    "4ee2505bb1b7b1e7b524a992b5409a39636bfe7e",
+                ),
+                (
+                    "private.torrent",
+                    "040012eec77d974a16bf6b99666ea2a587efb9bc",
+                ),
+                (
+                    "single_file.torrent",
+                    "bd9066cd3ec7cb89a3c93601c6c5fa34944501b7",
+                ),
+                (
+                    "dht_only.torrent",
+                    "9ee4a1e0f3a3a26c9b2dbd05153eb2deda91fa9c",
+                ),
+                (
+                    "v2_only.torrent",
+                    "3bcb9503b70d083e2f837558ff00c3cac0dcd59e",
+                ),
+                (
+                    "web_seeded.torrent",
+                    "bd9066cd3ec7cb89a3c93601c6c5fa34944501b7",
+                ),
+            ];
+            for (path, bencode) in fixtures() {
+                let name = path.file_name().unwrap().to_str().unwrap();
+                let Some((_, hex)) = expected.iter().find(|(x, _)| *x == name) else {
+                    continue;
+                };
+                let meta_info = MetaInfo::from_u8_len_check(&bencode).unwrap();
+                assert_eq!(&meta_info.info_hash_hex(), hex, "{}", name);
+                assert_eq!(meta_info.info_hash_base32().len(), 32, "{}", name);
+            }
+        }
+
+        #[test]
+        fn fixture_06() {
+            for (path, bencode) in fixtures() {
+                assert_eq!(
+                    MetaInfo::parse_fast(&bencode),
+                    MetaInfo::from_u8_len_check(&bencode),
+                    "{}",
+                    path.display()
+                );
+            }
+        }
+    }
+
+    mod info_hash_test {
+        use super::*;
+
+        // `info` keys are out of order, so re-encoding it would change the hash.
+        const UNSORTED: &[u8] =
+            b"d8:announce3:url4:infod4:name1:a6:lengthi1e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+
+        #[test]
+        fn info_hash_01() {
+            let info = &UNSORTED[22..UNSORTED.len() - 1];
+            let expect = InfoHash(Sha1::digest(info).into());
+            assert_eq!(
+                MetaInfo::from_u8_len_check(UNSORTED).unwrap().info_hash(),
+                expect
+            );
+        }
+
+        #[test]
+        fn info_hash_02() {
+            let element = decode_len_check(UNSORTED).unwrap();
+            let expect = InfoHash(Sha1::digest(encode(&element["info"])).into());
+            assert_eq!(
+                MetaInfo::from_element(&element).unwrap().info_hash(),
+                expect
+            );
+            assert_ne!(
+                MetaInfo::from_u8_len_check(UNSORTED).unwrap().info_hash(),
+                expect
+            );
+        }
+
+        #[test]
+        fn info_hash_03() {
+            let mut bencode = UNSORTED.to_vec();
+            bencode.extend_from_slice(b"garbage");
+            assert_eq!(
+                MetaInfo::from_u8_no_len_check(&bencode)
+                    .unwrap()
+                    .info_hash(),
+                MetaInfo::from_u8_len_check(UNSORTED).unwrap().info_hash()
+            );
+        }
+    }
+
+    mod to_magnet_test {
+        use super::fixture_test::fixture;
+        use super::*;
+
+        fn helper(announce: &str, extra: &str, info: &str) -> MetaInfo {
+            let bencode = format!(
+                "d8:announce{}:{}{}4:info{}e",
+                announce.len(),
+                announce,
+                extra,
+                info
+            );
+            return MetaInfo::from_u8_len_check(bencode.as_bytes()).unwrap();
+        }
+
+        const SINGLE: &str =
+            "d6:lengthi1e4:name3:a b12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae";
+
+        #[test]
+        fn to_magnet_01() {
+            let meta_info = helper("http://t/a", "", SINGLE);
+            let hash = meta_info.info_hash_hex();
+            assert_eq!(
+                meta_info.to_magnet(),
+                format!(
+                    "magnet:?xt=urn:btih:{}&dn=a%20b&tr=http%3A%2F%2Ft%2Fa",
+                    hash
+                )
+            );
+        }
+
+        #[test]
+        fn to_magnet_02() {
+            let meta_info = helper(
+                "http://t/a",
+                "13:announce-listll10:http://t/a10:http://t/bel10:http://t/cee",
+                SINGLE,
+            );
+            assert!(meta_info.to_magnet().ends_with(
+                "&dn=a%20b&tr=http%3A%2F%2Ft%2Fa&tr=http%3A%2F%2Ft%2Fb&tr=http%3A%2F%2Ft%2Fc"
+            ));
+        }
+
+        #[test]
+        fn to_magnet_03() {
+            let meta_info = helper(
+                "",
+                "",
+                "d5:filesld6:lengthi1e4:pathl1:aeee4:name4:dir!12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae",
+            );
+            assert_eq!(
+                meta_info.to_magnet(),
+                format!(
+                    "magnet:?xt=urn:btih:{}&dn=dir%21",
+                    meta_info.info_hash_hex()
+                )
+            );
+        }
+
+        #[test]
+        fn to_magnet_04() {
+            let meta_info = MetaInfo::from_u8(&fixture("v2_only.torrent")).unwrap();
+            let magnet = meta_info.to_magnet();
+            assert!(magnet.starts_with(&format!(
+                "magnet:?xt=urn:btmh:1220{}&dn=v2dir",
+                hex_encode(&meta_info.info_hash_v2().unwrap())
+            )));
+            assert!(!magnet.contains("urn:btih:"));
+
+            let meta_info = MetaInfo::from_u8(&fixture("hybrid.torrent")).unwrap();
+            assert!(meta_info.to_magnet().starts_with(&format!(
+                "magnet:?xt=urn:btih:{}&xt=urn:btmh:1220{}&",
+                meta_info.info_hash_hex(),
+                hex_encode(&meta_info.info_hash_v2().unwrap())
+            )));
+        }
+    }
+
+    mod from_magnet_test {
+        use super::*;
+
+        const HEX: &str = "4de9b0e9855b349178fb7a42f37dc0f2fac3018d";
+
+        fn hash() -> InfoHash {
+            return HEX.parse().unwrap();
+        }
+
+        #[test]
+        fn from_magnet_01() {
+            let uri = format!(
+                "magnet:?xt=urn:btih:{}&dn=a%20b+c&tr=http%3A%2F%2Ft%2Fa&tr=udp://t:80",
+                HEX
+            );
+            assert_eq!(
+                MetaInfo::from_magnet(&uri),
+                Some(PartialMetaInfo {
+                    info_hash: hash(),
+                    name: Some("a b c".to_string()),
+                    trackers: vec!["http://t/a".to_string(), "udp://t:80".to_string()],
+                })
+            );
+        }
+
+        #[test]
+        fn from_magnet_02() {
+            let base32 = hash().to_base32();
+            for uri in [
+                format!("magnet:?xt=urn:btih:{}", HEX.to_uppercase()),
+                format!("magnet:?xt=urn:btih:{}", base32),
+                format!("magnet:?xt=urn:btih:{}", base32.to_lowercase()),
+                format!("magnet:?xl=1&xt=urn:btmh:1220aa&xt=urn:btih:{}&x.pe=1", HEX),
+            ] {
+                let partial = MetaInfo::from_magnet(&uri).unwrap();
+                assert_eq!(partial.info_hash, hash(), "{}", uri);
+                assert_eq!(partial.name, None);
+                assert!(partial.trackers.is_empty());
+            }
+        }
+
+        #[test]
+        fn from_magnet_03() {
+            for uri in [
+                "".to_string(),
+                format!("http://x/?xt=urn:btih:{}", HEX),
+                "magnet:?dn=a".to_string(),
+                "magnet:?xt=urn:btih:4de9".to_string(),
+                format!("magnet:?xt=urn:btih:{}zz", &HEX[..38]),
+                format!("magnet:?xt=urn:btih:{}&tr=%zz", HEX),
+                format!("magnet:?xt=urn:btih:{}&dn=%ff", HEX),
+            ] {
+                assert_eq!(MetaInfo::from_magnet(&uri), None, "{}", uri);
+            }
+        }
+
+        #[test]
+        fn from_magnet_04() {
+            let bencode = b"d8:announce10:http://t/a13:announce-listll10:http://t/bee4:infod6:lengthi1e4:name3:a b12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            let meta_info = MetaInfo::from_u8_len_check(bencode).unwrap();
+            let partial = MetaInfo::from_magnet(&meta_info.to_magnet()).unwrap();
+            assert_eq!(partial.info_hash, meta_info.info_hash());
+            assert_eq!(partial.name.as_deref(), Some("a b"));
+            assert_eq!(partial.trackers, vec!["http://t/a", "http://t/b"]);
+        }
+    }
+
+    mod accessor_test {
+        use super::*;
+
+        #[test]
+        fn accessor_01() {
+            let bencode = b"d8:announce10:http://t/a13:announce-listll10:http://t/aee7:comment2:hi10:created by4:test13:creation datei1700000000e8:encoding5:UTF-84:infod6:lengthi3e6:md5sum2:ab4:name3:a b12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa7:privatei1eee";
+            let meta_info = MetaInfo::from_u8_len_check(bencode).unwrap();
+            assert_eq!(meta_info.name(), "a b");
+            assert_eq!(meta_info.announce(), Some("http://t/a"));
+            assert_eq!(
+                meta_info.announce_list(),
+                Some([vec!["http://t/a".to_string()]].as_slice())
+            );
+            assert_eq!(meta_info.comment(), Some("hi"));
+            assert_eq!(meta_info.created_by(), Some("test"));
+            assert_eq!(meta_info.creation_date(), Some(1700000000));
+            assert_eq!(
+                meta_info.creation_time(),
+                Some(UNIX_EPOCH + Duration::from_secs(1700000000))
+            );
+            assert_eq!(meta_info.encoding(), Some("UTF-8"));
+
+            let common_file_info = meta_info.info().common_file_info();
+            assert_eq!(common_file_info.piece_length(), 16384);
+            assert_eq!(
+                common_file_info.pieces(),
+                [b"aaaaaaaaaaaaaaaaaaaa".to_vec()]
+            );
+            assert!(common_file_info.is_private());
+            let FileInfo::SingleFile(info) = meta_info.info() else {
+                panic!("expected single-file info");
+            };
+            assert_eq!(info.length(), 3);
+            assert_eq!(info.md5sum(), Some("ab"));
+            assert_eq!(meta_info.total_size(), 3);
+        }
+
+        #[test]
+        fn accessor_02() {
+            let bencode = b"d8:announce0:4:infod5:filesld6:lengthi1e4:pathl1:a1:beed6:lengthi2e6:md5sum2:cd4:pathl1:ceee4:name3:dir12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            let meta_info = MetaInfo::from_u8_len_check(bencode).unwrap();
+            assert_eq!(meta_info.name(), "dir");
+            assert_eq!(meta_info.announce_list(), None);
+            assert_eq!(meta_info.comment(), None);
+            assert_eq!(meta_info.creation_time(), None);
+            assert!(!meta_info.info().common_file_info().is_private());
+            let FileInfo::MultipleFile(info) = meta_info.info() else {
+                panic!("expected multi-file info");
+            };
+            assert_eq!(info.files().len(), 2);
+            assert_eq!(info.files()[0].path(), ["a", "b"]);
+            assert_eq!(info.files()[0].md5sum(), None);
+            assert_eq!(info.files()[1].length(), 2);
+            assert_eq!(info.files()[1].md5sum(), Some("cd"));
+            assert_eq!(meta_info.total_size(), 3);
+        }
+    }
+
+    mod total_size_test {
+        use super::*;
+
+        fn huge_files(file_count: usize) -> Vec<u8> {
+            let file = format!("d6:lengthi{}e4:pathl1:bee", i64::MAX);
+            return format!(
+                "d4:infod5:filesl{}e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+                file.repeat(file_count)
+            )
+            .into_bytes();
+        }
+
+        #[test]
+        fn total_size_01() {
+            // The second file is a BEP 47 padding file, which still takes up piece data.
+            let bencode = b"d8:announce0:4:infod5:filesld6:lengthi3000000000e4:pathl1:aeed4:attr1:p6:lengthi5e4:pathl4:.pad1:5eed6:lengthi7e4:pathl1:beee4:name3:dir12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            let meta_info = MetaInfo::from_u8_len_check(bencode).unwrap();
+            assert_eq!(meta_info.total_size(), 3000000012);
+        }
+
+        #[test]
+        fn total_size_02() {
+            let meta_info = MetaInfo::from_u8(&huge_files(2)).unwrap();
+            assert_eq!(meta_info.total_size(), 2 * i64::MAX as u64);
+            assert_eq!(meta_info.content_size(), 2 * i64::MAX as u64);
+        }
+
+        #[test]
+        fn total_size_03() {
+            // Three files of `i64::MAX` bytes overflow `u64`.
+            assert_eq!(
+                MetaInfo::from_u8(&huge_files(3)),
+                Err(MetaInfoError::TotalSizeOverflow)
+            );
+            assert_eq!(MetaInfo::parse_fast(&huge_files(3)), None);
+        }
+    }
+
+    mod piece_count_test {
+        use super::*;
+
+        fn helper(length: u64, piece_length: usize, pieces: usize) -> MetaInfo {
+            let bencode = single_file_torrent(length, piece_length, pieces, "", "");
+            return MetaInfo::from_u8_len_check(&bencode).unwrap();
+        }
+
+        #[test]
+        fn piece_count_01() {
+            let meta_info = helper(40000, 16384, 3);
+            assert_eq!(meta_info.piece_count(), 3);
+            assert_eq!(meta_info.piece_length(), 16384);
+            assert_eq!(meta_info.last_piece_length(), 40000 - 2 * 16384);
+        }
+
+        #[test]
+        fn piece_count_02() {
+            assert_eq!(helper(32768, 16384, 2).last_piece_length(), 16384);
+            assert_eq!(helper(0, 16384, 0).last_piece_length(), 0);
+            assert_eq!(helper(1, 0, 1).last_piece_length(), 0);
+        }
+
+        #[test]
+        fn piece_count_03() {
+            let mut pieces = b"a".repeat(20);
+            pieces.extend_from_slice(&b"b".repeat(20));
+            let mut bencode =
+                b"d4:infod6:lengthi20000e4:name1:a12:piece lengthi16384e6:pieces40:".to_vec();
+            bencode.extend_from_slice(&pieces);
+            bencode.extend_from_slice(b"ee");
+            let meta_info = MetaInfo::from_u8(&bencode).unwrap();
+            assert_eq!(meta_info.piece_hash(0), Some(&pieces[..20]));
+            assert_eq!(meta_info.piece_hash(1), Some(&pieces[20..]));
+            assert_eq!(meta_info.piece_hash(2), None);
+            assert_eq!(meta_info.piece_hash(usize::MAX), None);
+        }
+
+        #[test]
+        fn piece_count_04() {
+            let meta_info = helper(40000, 16384, 3);
+            assert_eq!(meta_info.pieces_for_range(0, 16384), 0..1);
+            assert_eq!(meta_info.pieces_for_range(0, 16385), 0..2);
+            assert_eq!(meta_info.pieces_for_range(16383, 2), 0..2);
+            assert_eq!(meta_info.pieces_for_range(16384, 16384), 1..2);
+            assert_eq!(meta_info.pieces_for_range(20000, 20000), 1..3);
+            assert_eq!(meta_info.pieces_for_range(39999, 1), 2..3);
+        }
+
+        #[test]
+        fn piece_count_05() {
+            assert_eq!(helper(40000, 16384, 3).pieces_for_range(100, 0), 0..0);
+            assert_eq!(helper(1, 0, 1).pieces_for_range(0, 1), 0..0);
+            assert_eq!(
+                helper(40000, 16384, 3).pieces_for_range(u64::MAX - 1, 10),
+                (u64::MAX / 16384) as usize..(u64::MAX / 16384 + 1) as usize
+            );
+        }
+    }
+
+    mod to_bencode_test {
+        use super::*;
+
+        // `info` has unsorted and unknown keys, which must survive as they are.
+        const BENCODE: &[u8] = b"d8:announce10:http://t/a13:announce-listll10:http://t/aee7:comment2:hi13:creation datei1700000000e4:infod4:name1:a6:lengthi1e1:x1:y12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+
+        #[test]
+        fn to_bencode_01() {
+            let meta_info = MetaInfo::from_u8_len_check(BENCODE).unwrap();
+            assert_eq!(meta_info.to_bencode(), BENCODE);
+        }
+
+        #[test]
+        fn to_bencode_02() {
+            let mut meta_info = MetaInfo::from_u8_len_check(BENCODE).unwrap();
+            meta_info.comment = None;
+            meta_info.announce = Some("http://t/b".to_string());
+            let bencode = meta_info.to_bencode();
+            let parsed = MetaInfo::from_u8_len_check(&bencode).unwrap();
+            assert_eq!(parsed.info_hash(), meta_info.info_hash());
+            assert_eq!(parsed.announce(), Some("http://t/b"));
+            assert_eq!(parsed.comment(), None);
+            assert_eq!(parsed, meta_info);
+        }
+
+        #[test]
+        fn to_bencode_03() {
+            let single: &[u8] = b"d8:announce0:4:infod6:lengthi1e6:md5sum2:ab4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa7:privatei1eee";
+            let multiple: &[u8] = b"d8:announce0:4:infod5:filesld6:lengthi1e6:md5sum2:ab4:pathl1:a1:beed6:lengthi2e4:pathl1:ceee4:name3:dir12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            for canonical in [single, multiple] {
+                let parsed = MetaInfo::from_u8_len_check(canonical).unwrap();
+                let meta_info = MetaInfo::new(parsed.info.clone(), Some(""));
+                assert_eq!(meta_info.info_hash(), parsed.info_hash());
+                assert_eq!(meta_info.to_bencode(), canonical);
+            }
+        }
+
+        #[test]
+        fn to_bencode_04() {
+            let dir = TempDir::new("to-bencode");
+            let path = dir.path().join("a.torrent");
+            let meta_info = MetaInfo::from_u8_len_check(BENCODE).unwrap();
+            meta_info.write_to_file(&path).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), BENCODE);
+        }
+    }
+
+    mod announce_test {
+        use super::fixture_test::fixture;
+        use super::*;
+
+        const TRACKERLESS: &[u8] = b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae5:nodesll9:127.0.0.1i6881eeee";
+
+        #[test]
+        fn announce_01() {
+            let meta_info = MetaInfo::from_u8_len_check(TRACKERLESS).unwrap();
+            assert_eq!(meta_info.announce(), None);
+            assert_eq!(
+                meta_info.to_magnet(),
+                format!("magnet:?xt=urn:btih:{}&dn=a", meta_info.info_hash_hex())
+            );
+            assert!(!meta_info.to_bencode().starts_with(b"d8:announce"));
+        }
+
+        #[test]
+        fn announce_02() {
+            let bencode = b"d8:announcei1e4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            assert_eq!(MetaInfo::from_u8_len_check(bencode), None);
+        }
+
+        #[test]
+        fn announce_03() {
+            let bencode = fixture("dht_only.torrent");
+            let meta_info = MetaInfo::from_u8_len_check(&bencode).unwrap();
+            assert_eq!(meta_info.announce(), None);
+            assert_eq!(meta_info.announce_list(), None);
+            assert_eq!(meta_info.name(), "dht.bin");
+            assert_eq!(
+                meta_info.nodes(),
+                Some(
+                    [
+                        ("router.example".to_string(), 6881),
+                        ("10.0.0.1".to_string(), 6881)
+                    ]
+                    .as_slice()
+                )
+            );
+            assert_eq!(meta_info.to_bencode(), bencode);
+        }
+    }
+
+    mod nodes_test {
+        use super::*;
+
+        fn helper(nodes: &str) -> MetaInfo {
+            let bencode = single_file_torrent(1, 16384, 1, "", &format!("5:nodes{}", nodes));
+            return MetaInfo::from_u8_len_check(&bencode).unwrap();
+        }
+
+        #[test]
+        fn nodes_01() {
+            assert_eq!(
+                helper("ll9:127.0.0.1i6881eel3:::1i1eee").nodes(),
+                Some([("127.0.0.1".to_string(), 6881), ("::1".to_string(), 1)].as_slice())
+            );
+            assert_eq!(helper("le").nodes(), Some([].as_slice()));
+        }
+
+        #[test]
+        fn nodes_02() {
+            // Malformed nodes are ignored rather than failing the whole torrent.
+            for nodes in [
+                "i1e",
+                "ll1:ai65536eee",
+                "ll1:ai-1eee",
+                "lli1e1:aee",
+                "ll1:aee",
+                "ll1:ai1ei2eee",
+            ] {
+                assert_eq!(helper(nodes).nodes(), None, "{}", nodes);
+            }
+        }
+    }
+
+    mod url_list_test {
+        use super::*;
+
+        fn helper(url_list: &str) -> MetaInfo {
+            let bencode = single_file_torrent(1, 16384, 1, "", &format!("8:url-list{}", url_list));
+            return MetaInfo::from_u8_len_check(&bencode).unwrap();
+        }
+
+        #[test]
+        fn url_list_01() {
+            let meta_info = helper("10:http://m/a");
+            assert_eq!(
+                meta_info.url_list(),
+                Some(["http://m/a".to_string()].as_slice())
+            );
+            // A single URL is written back as a single URL, and a list of one URL as a list.
+            assert_eq!(
+                meta_info.to_bencode(),
+                single_file_torrent(1, 16384, 1, "", "8:url-list10:http://m/a")
+            );
+            assert_eq!(
+                helper("l10:http://m/ae").to_bencode(),
+                single_file_torrent(1, 16384, 1, "", "8:url-listl10:http://m/ae")
+            );
+        }
+
+        #[test]
+        fn url_list_02() {
+            let meta_info = helper("l10:http://m/a0:9:ftp://m/be");
+            assert_eq!(
+                meta_info.url_list(),
+                Some(["http://m/a".to_string(), "ftp://m/b".to_string()].as_slice())
+            );
+            let round_trip = MetaInfo::from_u8_len_check(&meta_info.to_bencode()).unwrap();
+            assert_eq!(round_trip.url_list(), meta_info.url_list());
+        }
+
+        #[test]
+        fn url_list_03() {
+            assert_eq!(helper("0:").url_list(), None);
+            assert_eq!(
+                helper("0:").to_bencode(),
+                single_file_torrent(1, 16384, 1, "", "")
+            );
+            assert_eq!(helper("le").url_list(), Some([].as_slice()));
+            assert_eq!(helper("i1e").url_list(), None);
+            assert_eq!(helper("li1ee").url_list(), None);
+        }
+    }
+
+    mod http_seeds_test {
+        use super::fixture_test::fixture;
+        use super::*;
+
+        #[test]
+        fn http_seeds_01() {
+            let bencode = fixture("web_seeded.torrent");
+            let meta_info = MetaInfo::from_u8_len_check(&bencode).unwrap();
+            assert_eq!(
+                meta_info.http_seeds(),
+                Some(["http://seed.example/seed.php".to_string()].as_slice())
+            );
+            assert_eq!(
+                meta_info.url_list(),
+                Some(
+                    [
+                        "http://mirror.example/files/".to_string(),
+                        "ftp://mirror.example/pub/".to_string()
+                    ]
+                    .as_slice()
+                )
+            );
+            assert_eq!(meta_info.to_bencode(), bencode);
+        }
+
+        #[test]
+        fn http_seeds_02() {
+            let bencode = b"d9:httpseeds10:http://s/a4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            let meta_info = MetaInfo::from_u8_len_check(bencode).unwrap();
+            assert_eq!(meta_info.http_seeds(), None);
+            assert_eq!(meta_info.url_list(), None);
+        }
+    }
+
+    mod from_u8_test {
+        use super::*;
+
+        fn helper(bencode: &[u8], expect: MetaInfoError) {
+            assert_eq!(
+                MetaInfo::from_u8(bencode),
+                Err(expect),
+                "{}",
+                String::from_utf8_lossy(bencode)
+            );
+            assert_eq!(MetaInfo::from_u8_len_check(bencode), None);
+        }
+
+        #[test]
+        fn from_u8_01() {
+            let bencode = b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            assert_eq!(
+                MetaInfo::from_u8(bencode).ok(),
+                MetaInfo::from_u8_len_check(bencode)
+            );
+            assert!(MetaInfo::from_u8(bencode).is_ok());
+        }
+
+        #[test]
+        fn from_u8_02() {
+            helper(
+                b"d4:infod",
+                MetaInfoError::Decode(DecodeError::UnexpectedEof),
+            );
+            helper(
+                b"d1:ai1ee1:b",
+                MetaInfoError::Decode(DecodeError::TrailingData { offset: 8 }),
+            );
+        }
+
+        #[test]
+        fn from_u8_03() {
+            helper(b"d1:ai1ee", MetaInfoError::MissingField("info"));
+            helper(
+                b"d4:infod4:name1:a12:piece lengthi1e6:pieces0:ee",
+                MetaInfoError::NoLengthOrFiles,
+            );
+            helper(
+                b"d4:infod6:lengthi1e4:name1:a6:pieces0:ee",
+                MetaInfoError::MissingField("piece length"),
+            );
+            helper(
+                b"d4:infod5:filesld6:lengthi1eee4:name1:a12:piece lengthi1e6:pieces0:ee",
+                MetaInfoError::MissingField("path"),
+            );
+        }
+
+        #[test]
+        fn from_u8_04() {
+            helper(b"li1ee", MetaInfoError::NotADictionary);
+            helper(b"i1e", MetaInfoError::NotADictionary);
+            helper(b"4:spam", MetaInfoError::NotADictionary);
+            helper(b"d4:infoi1ee", MetaInfoError::WrongType("info"));
+            helper(
+                b"d8:announcei1e4:infod6:lengthi1e4:name1:a12:piece lengthi1e6:pieces0:ee",
+                MetaInfoError::WrongType("announce"),
+            );
+            helper(
+                b"d4:infod6:lengthi-1e4:name1:a12:piece lengthi1e6:pieces0:ee",
+                MetaInfoError::WrongType("length"),
+            );
+            helper(
+                b"d4:infod5:filesi1e4:name1:a12:piece lengthi1e6:pieces0:ee",
+                MetaInfoError::WrongType("files"),
+            );
+            helper(
+                b"d13:announce-list1:a4:infod6:lengthi1e4:name1:a12:piece lengthi1e6:pieces0:ee",
+                MetaInfoError::WrongType("announce-list"),
+            );
+        }
+
+        #[test]
+        fn from_u8_05() {
+            helper(
+                b"d4:infod6:lengthi1e4:name1:a12:piece lengthi1e6:pieces3:abcee",
+                MetaInfoError::BadPiecesLength,
+            );
+        }
+
+        #[test]
+        fn from_u8_06() {
+            let mut bencode = b"d4:infod6:lengthi1e4:name1:".to_vec();
+            bencode.push(0xff);
+            bencode.extend_from_slice(b"12:piece lengthi1e6:pieces0:ee");
+            helper(&bencode, MetaInfoError::InvalidName);
+            assert_eq!(
+                MetaInfoError::InvalidName.to_string(),
+                "field 'name' is not valid UTF-8"
+            );
+            assert_eq!(
+                MetaInfoError::MissingField("info").to_string(),
+                "missing field 'info'"
+            );
+        }
+    }
+
+    mod safe_path_test {
+        use super::*;
+
+        fn helper(path: &[&str]) -> Option<PathBuf> {
+            let path = path.iter().map(|x| x.to_string()).collect();
+            return MultipleFileInfoFile::new(1, path, None).safe_path(Path::new("base"));
+        }
+
+        #[test]
+        fn safe_path_01() {
+            assert_eq!(helper(&["a"]), Some(PathBuf::from("base/a")));
+            assert_eq!(helper(&["a", "b.txt"]), Some(PathBuf::from("base/a/b.txt")));
+            assert_eq!(
+                helper(&["...", "a..b"]),
+                Some(PathBuf::from("base/.../a..b"))
+            );
+        }
+
+        #[test]
+        fn safe_path_02() {
+            for path in [
+                &[][..],
+                &["..", "..", "etc", "passwd"],
+                &["a", ".."],
+                &["."],
+                &[""],
+                &["a", ""],
+                &["/etc/passwd"],
+                &["a/../../b"],
+                &["a\\..\\b"],
+                &["C:\\Windows"],
+                &["a\0b"],
+            ] {
+                assert_eq!(helper(path), None, "{:?}", path);
+            }
+        }
+    }
+
+    mod v2_test {
+        use super::fixture_test::fixture;
+        use super::*;
+        use crate::utils::hex_decode;
+
+        fn root(hex: &str) -> [u8; 32] {
+            return hex_decode(hex).unwrap().try_into().unwrap();
+        }
+
+        #[test]
+        fn v2_01() {
+            // Roots computed independently with Python's hashlib.
+            let big_root = root("fac26c024b79547f3cba71f5f7d8b529997503683fc676f5a534b6feb8daccb8");
+            let small_root =
+                root("6a13a3f389e37acd64ad9e591cbc5032247178ee12ad05bcf329ab51bc78f3cc");
+            let bencode = fixture("v2_only.torrent");
+            let meta_info = MetaInfo::from_u8(&bencode).unwrap();
+            assert_eq!(meta_info.meta_version(), Some(2));
+            assert_eq!(meta_info.name(), "v2dir");
+            assert_eq!(meta_info.piece_length(), 16384);
+            assert_eq!(meta_info.piece_count(), 0);
+            assert_eq!(meta_info.total_size(), 40009);
+
+            let files = meta_info.file_tree().unwrap().files();
+            let files: Vec<_> = files
+                .into_iter()
+                .map(|(path, file)| (path, file.length(), file.pieces_root()))
+                .collect();
+            assert_eq!(
+                files,
+                vec![
+                    (vec!["big.bin".to_string()], 40000, Some(&big_root)),
+                    (vec!["sub".to_string(), "empty.txt".to_string()], 0, None),
+                    (
+                        vec!["sub".to_string(), "small.txt".to_string()],
+                        9,
+                        Some(&small_root)
+                    ),
+                ]
+            );
+
+            let FileInfo::MultipleFile(info) = meta_info.info() else {
+                panic!("expected multi-file info");
+            };
+            assert_eq!(info.files()[2].path(), ["sub", "small.txt"]);
+
+            let piece_layers = meta_info.piece_layers().unwrap();
+            assert_eq!(piece_layers.len(), 1);
+            assert_eq!(piece_layers[&big_root].len(), 3);
+            assert_eq!(meta_info.to_bencode(), bencode);
+        }
+
+        #[test]
+        fn v2_02() {
+            let bencode = b"d4:infod9:file treed1:ad0:d6:lengthi5eeee12:meta versioni2e4:name1:a12:piece lengthi16384eee";
+            let meta_info = MetaInfo::from_u8(bencode).unwrap();
+            let FileInfo::SingleFile(info) = meta_info.info() else {
+                panic!("expected single-file info");
+            };
+            assert_eq!(info.length(), 5);
+            assert_eq!(
+                meta_info.file_tree().unwrap().files()[0].1.pieces_root(),
+                None
+            );
+        }
+
+        #[test]
+        fn v2_03() {
+            let helper = |file_tree: &str, piece_layers: &str| {
+                let bencode = format!(
+                    "d4:infod9:file tree{}12:meta versioni2e4:name1:a12:piece lengthi16384ee{}e",
+                    file_tree, piece_layers
+                );
+                return MetaInfo::from_u8(bencode.as_bytes());
+            };
+            assert_eq!(
+                helper("i1e", ""),
+                Err(MetaInfoError::WrongType("file tree"))
+            );
+            assert_eq!(
+                helper("d1:ad0:d11:pieces root1:xeee", ""),
+                Err(MetaInfoError::MissingField("length"))
+            );
+            assert_eq!(
+                helper("d1:ad0:d6:lengthi1e11:pieces root1:xeee", ""),
+                Err(MetaInfoError::WrongType("pieces root"))
+            );
+            assert_eq!(
+                helper("d1:ad0:d6:lengthi1eeee", "12:piece layersd1:x0:e"),
+                Err(MetaInfoError::WrongType("piece layers"))
+            );
+            assert_eq!(
+                MetaInfo::from_u8(b"d4:infod12:meta versioni2e4:name1:a12:piece lengthi16384eee"),
+                Err(MetaInfoError::MissingField("file tree"))
+            );
+        }
+
+        #[test]
+        fn v2_04() {
+            // Hybrid torrents keep their v1 layout and get the v2 file tree in addition.
+            let bencode = fixture("hybrid.torrent");
+            let meta_info = MetaInfo::from_u8(&bencode).unwrap();
+            assert_eq!(meta_info.meta_version(), Some(2));
+            assert_eq!(meta_info.piece_count(), 1);
+            assert_eq!(meta_info.file_tree().unwrap().files().len(), 1);
+            assert_eq!(meta_info.piece_layers().map(|x| x.len()), Some(0));
+
+            let single = fixture("single_file.torrent");
+            assert_eq!(MetaInfo::from_u8(&single).unwrap().file_tree(), None);
+        }
+    }
+
+    mod hybrid_test {
+        use super::fixture_test::fixture;
+        use super::*;
+        use crate::utils::hex_encode;
+
+        #[test]
+        fn hybrid_01() {
+            // Computed independently with Python's hashlib over the `info` bytes.
+            let meta_info = MetaInfo::from_u8(&fixture("hybrid.torrent")).unwrap();
+            assert!(meta_info.is_v1() && meta_info.is_v2() && meta_info.is_hybrid());
+            assert_eq!(
+                meta_info.info_hash_hex(),
+                "ec867b5cc8e39d04b59c3f449d2bd74cca0da9fa"
+            );
+            assert_eq!(
+                hex_encode(&meta_info.info_hash_v2().unwrap()),
+                "ff44abc329cdcd2c7c4d3456155456714a476a2eb8d934389b625ab033e8dcc6"
+            );
+        }
+
+        #[test]
+        fn hybrid_02() {
+            let meta_info = MetaInfo::from_u8(&fixture("v2_only.torrent")).unwrap();
+            assert!(!meta_info.is_v1() && meta_info.is_v2() && !meta_info.is_hybrid());
+            assert_eq!(
+                hex_encode(&meta_info.info_hash_v2().unwrap()),
+                "6189c3f64095e98f06dfcb773d3b841342a3f4b36ef3f7f962ea95cb8f16711e"
+            );
+
+            let meta_info = MetaInfo::from_u8(&fixture("single_file.torrent")).unwrap();
+            assert!(meta_info.is_v1() && !meta_info.is_v2() && !meta_info.is_hybrid());
+            assert_eq!(meta_info.info_hash_v2(), None);
+        }
+
+        #[test]
+        fn hybrid_03() {
+            // The v2 hash is over the exact bytes even when `info` is not canonical.
+            let bencode = b"d4:infod4:name1:a9:file treed1:ad0:d6:lengthi1eeee6:lengthi1e12:meta versioni2e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            let meta_info = MetaInfo::from_u8(bencode).unwrap();
+            let expect: [u8; 32] = Sha256::digest(&bencode[7..bencode.len() - 1]).into();
+            assert_eq!(meta_info.info_hash_v2(), Some(expect));
+            assert!(meta_info.is_hybrid());
+        }
+    }
+
+    mod validate_test {
+        use super::*;
+
+        fn helper(length: u64, piece_length: usize, pieces: usize) -> Result<(), MetaInfoError> {
+            let bencode = single_file_torrent(length, piece_length, pieces, "", "");
+            return MetaInfo::from_u8(&bencode).unwrap().validate();
+        }
+
+        #[test]
+        fn validate_01() {
+            assert_eq!(helper(40000, 16384, 3), Ok(()));
+            assert_eq!(helper(32768, 16384, 2), Ok(()));
+            assert_eq!(helper(1, 16384, 1), Ok(()));
+            assert_eq!(helper(0, 16384, 0), Ok(()));
+            assert_eq!(helper(0, 0, 0), Ok(()));
+        }
+
+        #[test]
+        fn validate_02() {
+            assert_eq!(
+                helper(40000, 16384, 2),
+                Err(MetaInfoError::PieceCountMismatch {
+                    expected: 3,
+                    actual: 2
+                })
+            );
+            assert_eq!(
+                helper(32768, 16384, 3),
+                Err(MetaInfoError::PieceCountMismatch {
+                    expected: 2,
+                    actual: 3
+                })
+            );
+            assert_eq!(helper(1, 0, 1), Err(MetaInfoError::InvalidPieceLength));
+        }
+
+        #[test]
+        fn validate_03() {
+            let bencode = fixture_test::fixture("v2_only.torrent");
+            assert_eq!(MetaInfo::from_u8(&bencode).unwrap().validate(), Ok(()));
+            assert_eq!(
+                MetaInfoError::PieceCountMismatch {
+                    expected: 3,
+                    actual: 2
+                }
+                .to_string(),
+                "content needs 3 pieces, but 'pieces' has 2"
+            );
+        }
+
+        #[test]
+        fn validate_04() {
+            // Parsing rejects such lengths, but a `MetaInfo` built by hand can still have them.
+            let info = multiple_file_info(&[usize::MAX; 3], 16384, 1);
+            let meta_info = MetaInfo::new(info, None);
+            assert_eq!(meta_info.validate(), Err(MetaInfoError::TotalSizeOverflow));
+            assert_eq!(meta_info.total_size(), u64::MAX);
+        }
+    }
+
+    mod is_private_test {
+        use super::*;
+
+        fn helper(private: &str) -> bool {
+            let bencode = single_file_torrent(1, 16384, 1, private, "");
+            return MetaInfo::from_u8(&bencode).unwrap().is_private();
+        }
+
+        #[test]
+        fn is_private_01() {
+            assert!(helper("7:privatei1e"));
+            assert!(!helper(""));
+            assert!(!helper("7:privatei0e"));
+        }
+
+        #[test]
+        fn is_private_02() {
+            // Only 1 marks a torrent private; other values are treated like absent.
+            assert!(!helper("7:privatei2e"));
+            assert!(!helper("7:private1:1"));
+        }
+    }
+
+    mod utf8_variant_test {
+        use super::*;
+
+        // "é" in Latin-1 (0xe9), which is not valid UTF-8, next to its UTF-8 variant.
+        fn single(keys: &[u8]) -> Vec<u8> {
+            let mut bencode = b"d4:infod6:lengthi1e".to_vec();
+            bencode.extend_from_slice(keys);
+            bencode.extend_from_slice(b"12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee");
+            return bencode;
+        }
+
+        #[test]
+        fn utf8_variant_01() {
+            let meta_info =
+                MetaInfo::from_u8(&single(b"4:name1:\xe910:name.utf-82:\xc3\xa9")).unwrap();
+            assert_eq!(meta_info.name(), "\u{e9}");
+            assert_eq!(meta_info.info().raw_name(), b"\xe9");
+            assert_eq!(
+                MetaInfo::new(meta_info.info().clone(), None).info_hash(),
+                meta_info.info_hash()
+            );
+        }
+
+        #[test]
+        fn utf8_variant_02() {
+            let meta_info = MetaInfo::from_u8(&single(b"4:name1:a")).unwrap();
+            assert_eq!(meta_info.name(), "a");
+            assert_eq!(meta_info.info().raw_name(), b"a");
+
+            // An invalid `name.utf-8` falls back to `name`.
+            let meta_info = MetaInfo::from_u8(&single(b"4:name1:a10:name.utf-81:\xe9")).unwrap();
+            assert_eq!(meta_info.name(), "a");
+
+            let meta_info = MetaInfo::from_u8(&single(b"10:name.utf-81:b")).unwrap();
+            assert_eq!(meta_info.name(), "b");
+            assert_eq!(meta_info.info().raw_name(), b"b");
+
+            assert_eq!(
+                MetaInfo::from_u8(&single(b"4:name1:\xe9")),
+                Err(MetaInfoError::InvalidName)
+            );
+            assert_eq!(
+                MetaInfo::from_u8(&single(b"")),
+                Err(MetaInfoError::MissingField("name"))
+            );
+        }
+
+        #[test]
+        fn utf8_variant_03() {
+            let bencode = b"d4:infod5:filesld6:lengthi1e4:pathl1:d1:\xe9e10:path.utf-8l1:d2:\xc3\xa9eed6:lengthi1e4:pathl1:xeee4:name3:dir12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            let meta_info = MetaInfo::from_u8(bencode).unwrap();
+            let FileInfo::MultipleFile(info) = meta_info.info() else {
+                panic!("expected multi-file info");
+            };
+            assert_eq!(info.files()[0].path(), ["d", "\u{e9}"]);
+            assert_eq!(
+                info.files()[0].raw_path(),
+                [b"d".to_vec(), b"\xe9".to_vec()]
+            );
+            assert_eq!(info.files()[1].path(), ["x"]);
+            assert_eq!(
+                MetaInfo::new(meta_info.info().clone(), None).info_hash(),
+                meta_info.info_hash()
+            );
+
+            let bencode = b"d4:infod5:filesld6:lengthi1e4:pathl1:\xe9eee4:name3:dir12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            assert_eq!(
+                MetaInfo::from_u8(bencode),
+                Err(MetaInfoError::WrongType("path"))
+            );
+        }
+    }
+
+    mod announce_list_test {
+        use super::*;
+
+        fn bencode(announce_list: &str) -> Vec<u8> {
+            return format!(
+                "d8:announce3:t/a13:announce-list{}4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+                announce_list
+            )
+            .into_bytes();
+        }
+
+        fn tiers(tiers: &[&[&str]]) -> Vec<Vec<String>> {
+            return tiers
+                .iter()
+                .map(|tier| tier.iter().map(|x| x.to_string()).collect())
+                .collect();
+        }
+
+        #[test]
+        fn announce_list_01() {
+            let meta_info = MetaInfo::from_u8(&bencode("ll3:t/b3:t/ael0:el3:t/b0:ee")).unwrap();
+            assert_eq!(
+                meta_info.announce_list(),
+                Some(tiers(&[&["t/b", "t/a"], &["t/b"]]).as_slice())
+            );
+            assert_eq!(meta_info.tiers(), tiers(&[&["t/b", "t/a"], &["t/b"]]));
+
+            let err = MetaInfo::from_u8(&bencode("lle0:e")).unwrap_err();
+            assert_eq!(err, MetaInfoError::WrongType("announce-list"));
+        }
+
+        #[test]
+        fn announce_list_02() {
+            for announce_list in ["i1e", "l3:t/be", "lli1eee", "ll3:t/bei1ee"] {
+                assert_eq!(
+                    MetaInfo::from_u8(&bencode(announce_list)),
+                    Err(MetaInfoError::WrongType("announce-list")),
+                    "{}",
+                    announce_list
+                );
+            }
+        }
+
+        #[test]
+        fn announce_list_03() {
+            let meta_info = MetaInfo::from_u8(&bencode("llel0:ee")).unwrap();
+            assert_eq!(meta_info.announce_list(), None);
+            assert_eq!(meta_info.tiers(), tiers(&[&["t/a"]]));
+
+            let meta_info = MetaInfo::from_u8(&bencode("ll3:t/bee")).unwrap();
+            assert_eq!(meta_info.tiers(), tiers(&[&["t/a"], &["t/b"]]));
+        }
+
+        #[test]
+        fn announce_list_04() {
+            let options = ParseOptions {
+                dedup_announce_list: true,
+                ..Default::default()
+            };
+            let meta_info = MetaInfo::from_u8_with_options(
+                &bencode("ll3:t/b3:t/b3:t/cel3:t/cel3:t/dee"),
+                &options,
+            )
+            .unwrap();
+            assert_eq!(
+                meta_info.announce_list(),
+                Some(tiers(&[&["t/b", "t/c"], &["t/d"]]).as_slice())
+            );
+
+            let mut meta_info = MetaInfo::from_u8(&bencode("ll3:t/bel3:t/bee")).unwrap();
+            meta_info.dedup_announce_list();
+            assert_eq!(
+                meta_info.announce_list(),
+                Some(tiers(&[&["t/b"]]).as_slice())
+            );
+        }
+
+        #[test]
+        fn announce_list_05() {
+            let meta_info = MetaInfo::from_u8(&bencode("ll3:t/b3:t/cel3:t/dee")).unwrap();
+            let announce_tiers: Vec<&[String]> = meta_info.announce_tiers().collect();
+            assert_eq!(
+                announce_tiers,
+                tiers(&[&["t/a"], &["t/b", "t/c"], &["t/d"]])
+            );
+
+            let meta_info = MetaInfo::new(meta_info.info.clone(), None);
+            assert_eq!(meta_info.announce_tiers().count(), 0);
+        }
+
+        #[test]
+        fn announce_list_06() {
+            let meta_info = MetaInfo::from_u8(&bencode("ll3:t/b3:t/c3:t/del3:t/eee")).unwrap();
+            let mut bounds = Vec::<usize>::new();
+            let shuffled = meta_info.shuffled_announce(&mut |n| {
+                bounds.push(n);
+                return 0;
+            });
+            assert_eq!(bounds, vec![3, 2]);
+            assert_eq!(
+                shuffled,
+                tiers(&[&["t/a"], &["t/c", "t/d", "t/b"], &["t/e"]])
+            );
+
+            // Always picking the last index keeps the order.
+            let shuffled = meta_info.shuffled_announce(&mut |n| n - 1);
+            assert_eq!(shuffled, meta_info.tiers());
+        }
+
+        #[test]
+        fn announce_list_07() {
+            // Out-of-range indices from `rng` are reduced instead of panicking.
+            let meta_info = MetaInfo::from_u8(&bencode("ll3:t/b3:t/c3:t/del3:t/eee")).unwrap();
+            let shuffled = meta_info.shuffled_announce(&mut |n| n);
+            assert_eq!(shuffled, meta_info.shuffled_announce(&mut |_| 0));
+            let shuffled = meta_info.shuffled_announce(&mut |_| usize::MAX);
+            let mut tier = shuffled[1].clone();
+            tier.sort();
+            assert_eq!(tier, meta_info.tiers()[1]);
+        }
+    }
+
+    mod source_test {
+        use super::*;
+
+        #[test]
+        fn source_01() {
+            let bencode = b"d8:announce3:t/a4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa6:source3:ABCee";
+            let meta_info = MetaInfo::from_u8(bencode).unwrap();
+            assert_eq!(meta_info.source(), Some("ABC"));
+            assert_eq!(meta_info.info().common_file_info().source(), Some("ABC"));
+
+            let bencode = b"d8:announce3:t/a4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            assert_eq!(MetaInfo::from_u8(bencode).unwrap().source(), None);
+        }
+
+        #[test]
+        fn source_02() {
+            // Re-encoding keeps `source`, so the info hash stays the same.
+            let bencode = b"d8:announce3:t/a4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa6:source3:ABCee";
+            let meta_info = MetaInfo::from_u8(bencode).unwrap();
+            let rebuilt = MetaInfo::new(meta_info.info().clone(), meta_info.announce());
+            assert_eq!(rebuilt.source(), Some("ABC"));
+            assert_eq!(rebuilt.info_hash(), meta_info.info_hash());
+        }
+    }
+
+    mod file_piece_layout_test {
+        use super::*;
+
+        fn layout(lengths: &[usize], piece_length: usize) -> Vec<(usize, usize, usize, usize)> {
+            let total: usize = lengths.iter().sum();
+            let info = multiple_file_info(lengths, piece_length, total.div_ceil(piece_length));
+            let layout = MetaInfo::new(info, None).file_piece_layout();
+            for (i, x) in layout.iter().enumerate() {
+                assert_eq!(x.file_index, i);
+            }
+            return layout
+                .iter()
+                .map(|x| {
+                    (
+                        x.start_piece,
+                        x.end_piece,
+                        x.first_piece_offset,
+                        x.last_piece_end,
+                    )
+                })
+                .collect();
+        }
+
+        #[test]
+        fn file_piece_layout_01() {
+            // Files smaller than a piece share it.
+            assert_eq!(
+                layout(&[3, 4, 10], 8),
+                vec![(0, 1, 0, 3), (0, 1, 3, 7), (0, 3, 7, 1)]
+            );
+            // Files aligned to piece boundaries end at the end of their last piece.
+            assert_eq!(layout(&[16, 8], 8), vec![(0, 2, 0, 8), (2, 3, 0, 8)]);
+        }
+
+        #[test]
+        fn file_piece_layout_02() {
+            assert_eq!(
+                layout(&[8, 0, 5], 8),
+                vec![(0, 1, 0, 8), (1, 1, 0, 0), (1, 2, 0, 5)]
+            );
+            assert_eq!(layout(&[5, 20], 8), vec![(0, 1, 0, 5), (0, 4, 5, 1)]);
+        }
+
+        #[test]
+        fn file_piece_layout_03() {
+            let bencode = b"d8:announce3:t/a4:infod6:lengthi20000e4:name1:a12:piece lengthi16384e6:pieces40:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaee";
+            let meta_info = MetaInfo::from_u8(bencode).unwrap();
+            assert_eq!(
+                meta_info.file_piece_layout(),
+                vec![FilePieceRange {
+                    file_index: 0,
+                    start_piece: 0,
+                    end_piece: 2,
+                    first_piece_offset: 0,
+                    last_piece_end: 20000 - 16384,
+                }]
+            );
+        }
+
+        #[test]
+        fn file_piece_layout_04() {
+            // Lengths overflowing `u64` do not fit in the piece stream.
+            let info = multiple_file_info(&[usize::MAX; 3], 16384, 1);
+            assert_eq!(MetaInfo::new(info, None).file_piece_layout(), Vec::new());
+        }
+    }
+
+    mod verify_piece_test {
+        use super::*;
+
+        fn meta_info(content: &[u8], piece_length: usize) -> MetaInfo {
+            let pieces: Vec<u8> = content
+                .chunks(piece_length)
+                .flat_map(|x| Sha1::digest(x).to_vec())
+                .collect();
+            let info = single_file_info("a", content.len(), piece_length, &pieces);
+            return MetaInfo::new(info, None);
+        }
+
+        #[test]
+        fn verify_piece_01() {
+            let content = b"0123456789abcdefXYZ";
+            let meta_info = meta_info(content, 8);
+            assert!(meta_info.verify_piece(0, &content[..8]));
+            assert!(meta_info.verify_piece(1, &content[8..16]));
+            assert!(!meta_info.verify_piece(1, &content[..8]));
+            assert!(!meta_info.verify_piece(0, b"0123456x"));
+        }
+
+        #[test]
+        fn verify_piece_02() {
+            // The last piece is short, and padding it to `piece length` makes it wrong.
+            let content = b"0123456789abcdefXYZ";
+            let meta_info = meta_info(content, 8);
+            assert!(meta_info.verify_piece(2, b"XYZ"));
+            assert!(!meta_info.verify_piece(2, b"XYZ\0\0\0\0\0"));
+            assert!(!meta_info.verify_piece(3, b""));
+            assert!(!meta_info.verify_piece(usize::MAX, b"XYZ"));
+        }
+    }
+
+    mod zero_length_file_test {
+        use super::*;
+
+        const BENCODE: &[u8] = b"d8:announce3:t/a4:infod5:filesld6:lengthi5e4:pathl1:aeed6:lengthi0e4:pathl5:emptyeed6:lengthi4e4:pathl1:beee4:name3:dir12:piece lengthi4e6:pieces60:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaee";
+
+        #[test]
+        fn zero_length_file_01() {
+            let meta_info = MetaInfo::from_u8(BENCODE).unwrap();
+            let FileInfo::MultipleFile(info) = meta_info.info() else {
+                panic!("not multi-file");
+            };
+            let lengths: Vec<usize> = info.files().iter().map(|x| x.length()).collect();
+            assert_eq!(lengths, vec![5, 0, 4]);
+            assert_eq!(info.files()[1].path(), ["empty"]);
+            assert_eq!(meta_info.total_size(), 9);
+            assert_eq!(meta_info.piece_count(), 3);
+            assert_eq!(meta_info.last_piece_length(), 1);
+            assert!(meta_info.validate().is_ok());
+        }
+
+        #[test]
+        fn zero_length_file_02() {
+            // The empty file takes no bytes, so `b` starts right where `a` ends.
+            let layout = MetaInfo::from_u8(BENCODE).unwrap().file_piece_layout();
+            let layout: Vec<_> = layout
+                .iter()
+                .map(|x| {
+                    (
+                        x.start_piece,
+                        x.end_piece,
+                        x.first_piece_offset,
+                        x.last_piece_end,
+                    )
+                })
+                .collect();
+            assert_eq!(layout, vec![(0, 2, 0, 1), (1, 1, 1, 1), (1, 3, 1, 1)]);
+        }
+    }
+
+    mod builder_test {
+        use super::*;
+
+        fn info() -> FileInfo {
+            return single_file_info("a", 3, 16384, &[b'a'; 20]);
+        }
+
+        #[test]
+        fn builder_01() {
+            let meta_info = MetaInfoBuilder::new(info())
+                .announce("t/a")
+                .announce_list(vec![
+                    vec!["t/a".to_string()],
+                    vec![],
+                    vec!["t/b".to_string()],
+                ])
+                .creation_date(1700000000)
+                .comment("hi")
+                .created_by("test")
+                .encoding("UTF-8")
+                .build();
+            assert_eq!(meta_info.announce(), Some("t/a"));
+            assert_eq!(
+                meta_info.announce_list(),
+                Some([vec!["t/a".to_string()], vec!["t/b".to_string()]].as_slice())
+            );
+            assert_eq!(meta_info.creation_date(), Some(1700000000));
+            assert_eq!(meta_info.comment(), Some("hi"));
+            assert_eq!(meta_info.created_by(), Some("test"));
+            assert_eq!(meta_info.encoding(), Some("UTF-8"));
+            assert!(!meta_info.is_private());
+            assert_eq!(
+                meta_info.info_hash(),
+                MetaInfo::new(info(), None).info_hash()
+            );
+        }
+
+        #[test]
+        fn builder_02() {
+            let meta_info = MetaInfoBuilder::new(info())
+                .private(true)
+                .comment("hi")
+                .build();
+            assert!(meta_info.is_private());
+            assert_ne!(
+                meta_info.info_hash(),
+                MetaInfo::new(info(), None).info_hash()
+            );
+
+            let parsed = MetaInfo::from_u8(&meta_info.to_bencode()).unwrap();
+            assert_eq!(parsed, meta_info);
+            assert_eq!(parsed.announce(), None);
+        }
+
+        #[test]
+        fn builder_03() {
+            let common_file_info = CommonFileInfo::new(4, &[b'a'; 40], false).unwrap();
+            let files = vec![
+                MultipleFileInfoFile::new(6, vec!["a".to_string()], None),
+                MultipleFileInfoFile::new(2, vec!["sub".to_string(), "b".to_string()], None),
+            ];
+            let info = MultipleFileInfo::new(common_file_info.clone(), "dir", files);
+            let meta_info = MetaInfoBuilder::new(FileInfo::MultipleFile(info))
+                .announce("t/a")
+                .build();
+            let parsed = MetaInfo::from_u8(&meta_info.to_bencode()).unwrap();
+            assert_eq!(parsed, meta_info);
+            assert_eq!(parsed.name(), "dir");
+            assert_eq!(parsed.total_size(), 8);
+
+            let info = SingleFileInfo::new(common_file_info, "a", 8, Some("0123"));
+            let meta_info = MetaInfoBuilder::new(FileInfo::SingleFile(info)).build();
+            let parsed = MetaInfo::from_u8(&meta_info.to_bencode()).unwrap();
+            assert_eq!(parsed, meta_info);
+            let FileInfo::SingleFile(info) = parsed.info() else {
+                panic!("not single-file");
+            };
+            assert_eq!(
+                (info.name(), info.length(), info.md5sum()),
+                ("a", 8, Some("0123"))
+            );
+        }
+    }
+
+    mod create_from_path_test {
+        use super::*;
+        use std::fs;
+
+        #[test]
+        fn create_from_path_01() {
+            let dir = TempDir::new("create-single");
+            let root = dir.path();
+            fs::write(root.join("a.txt"), b"0123456789").unwrap();
+            let meta_info = MetaInfo::create_from_path(root.join("a.txt"), 4, Some("t/a")).unwrap();
+            assert_eq!(meta_info.name(), "a.txt");
+            assert_eq!(meta_info.announce(), Some("t/a"));
+            assert!(matches!(meta_info.info(), FileInfo::SingleFile(_)));
+            assert_eq!(meta_info.total_size(), 10);
+            assert_eq!(meta_info.piece_count(), 3);
+            assert!(meta_info.verify_piece(0, b"0123"));
+            assert!(meta_info.verify_piece(2, b"89"));
+            assert!(MetaInfo::from_u8(&meta_info.to_bencode()).is_ok());
+        }
+
+        #[test]
+        fn create_from_path_02() {
+            let dir = TempDir::new("create-multi");
+            let root = dir.path();
+            fs::create_dir_all(root.join("dir/sub")).unwrap();
+            fs::write(root.join("dir/b"), b"abcdef").unwrap();
+            fs::write(root.join("dir/a"), b"012345").unwrap();
+            fs::write(root.join("dir/sub/c"), b"").unwrap();
+            fs::write(root.join("dir/sub/d"), b"xy").unwrap();
+            let meta_info = MetaInfo::create_from_path(root.join("dir"), 4, None).unwrap();
+            let FileInfo::MultipleFile(info) = meta_info.info() else {
+                panic!("not multi-file");
+            };
+            let files: Vec<(&[String], usize)> = info
+                .files()
+                .iter()
+                .map(|x| (x.path(), x.length()))
+                .collect();
+            let path = |x: &[&str]| x.iter().map(|y| y.to_string()).collect::<Vec<_>>();
+            assert_eq!(
+                files,
+                vec![
+                    (path(&["a"]).as_slice(), 6),
+                    (path(&["b"]).as_slice(), 6),
+                    (path(&["sub", "c"]).as_slice(), 0),
+                    (path(&["sub", "d"]).as_slice(), 2),
+                ]
+            );
+            // Pieces run across files: "0123", "45ab", "cdef", "xy".
+            assert_eq!(meta_info.piece_count(), 4);
+            assert!(meta_info.verify_piece(1, b"45ab"));
+            assert!(meta_info.verify_piece(3, b"xy"));
+            let bitfield = meta_info.verified_bitfield(root).unwrap();
+            assert!((0..4).all(|x| bitfield.has(x)));
+        }
+
+        #[test]
+        fn create_from_path_03() {
+            let dir = TempDir::new("create-errors");
+            let root = dir.path();
+            fs::create_dir_all(root.join("empty")).unwrap();
+            fs::write(root.join("a"), b"0").unwrap();
+            let kind = |path: PathBuf, piece_length: usize| {
+                return MetaInfo::create_from_path(path, piece_length, None)
+                    .unwrap_err()
+                    .kind();
+            };
+            assert_eq!(kind(root.join("a"), 0), io::ErrorKind::InvalidInput);
+            assert_eq!(kind(root.join("empty"), 4), io::ErrorKind::InvalidInput);
+            assert_eq!(kind(root.join("missing"), 4), io::ErrorKind::NotFound);
+        }
+    }
+
+    mod announce_url_test {
+        use super::*;
+
+        #[test]
+        fn announce_url_01() {
+            let bencode = b"d8:announce10:http://t/a13:announce-listll10:http://t/bee4:infod6:lengthi3e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            let meta_info = MetaInfo::from_u8(bencode).unwrap();
+            let request = meta_info.announce_request(*b"-RT0001-123456789012", 6881);
+            assert_eq!(request.info_hash, meta_info.info_hash());
+            assert_eq!(request.left, 3);
+            let urls = meta_info.announce_urls(&request);
+            let info_hash = url_encode(meta_info.info_hash().as_bytes());
+            assert_eq!(urls.len(), 2);
+            assert_eq!(
+                urls[0],
+                vec![format!("http://t/a?info_hash={}&peer_id=-RT0001-123456789012&port=6881&uploaded=0&downloaded=0&left=3&compact=1", info_hash)]
+            );
+            assert_eq!(urls[1].len(), 1);
+            assert!(urls[1][0].starts_with(&format!("http://t/b?info_hash={}&", info_hash)));
+        }
+
+        #[test]
+        fn announce_url_02() {
+            let bencode = b"d4:infod6:lengthi3e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            let meta_info = MetaInfo::from_u8(bencode).unwrap();
+            let request = meta_info.announce_request([0; 20], 6881);
+            assert!(meta_info.announce_urls(&request).is_empty());
+        }
+    }
+
+    mod file_mode_test {
+        use super::*;
+
+        #[test]
+        fn file_mode_01() {
+            let bencode = b"d8:announce3:t/a4:infod5:filesld6:lengthi1e4:pathl1:beee6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            assert_eq!(
+                MetaInfo::from_u8(bencode),
+                Err(MetaInfoError::LengthAndFiles)
+            );
+        }
+
+        #[test]
+        fn file_mode_02() {
+            let bencode = b"d8:announce3:t/a4:infod4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            assert_eq!(
+                MetaInfo::from_u8(bencode),
+                Err(MetaInfoError::NoLengthOrFiles)
+            );
+            assert_eq!(
+                MetaInfoError::NoLengthOrFiles.to_string(),
+                "info has neither 'length' nor 'files'"
+            );
+        }
+    }
+
+    mod default_name_test {
+        use super::*;
+
+        #[test]
+        fn default_name_01() {
+            // The shared directory becomes the name, and is removed from the paths.
+            let bencode = b"d4:infod5:filesld6:lengthi1e4:pathl3:top1:aeed6:lengthi1e4:pathl3:top1:b1:ceee12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            let meta_info = MetaInfo::from_u8(bencode).unwrap();
+            assert_eq!(meta_info.name(), "top");
+            assert_eq!(meta_info.info().raw_name(), b"top");
+            let paths: Vec<Vec<String>> = meta_info.files().into_iter().map(|x| x.path).collect();
+            assert_eq!(paths, vec![vec!["top", "a"], vec!["top", "b", "c"]]);
+            let FileInfo::MultipleFile(info) = meta_info.info() else {
+                panic!("not a multi-file torrent");
+            };
+            assert_eq!(info.files()[1].raw_path(), [b"b".to_vec(), b"c".to_vec()]);
+            assert_eq!(MetaInfo::parse_fast(bencode), Some(meta_info));
+        }
+
+        #[test]
+        fn default_name_02() {
+            let mut bencode =
+                b"d4:infod9:file treed5:a.txtd0:d6:lengthi3e11:pieces root32:".to_vec();
+            bencode.extend_from_slice(&[0xab; 32]);
+            bencode.extend_from_slice(b"eee12:meta versioni2e12:piece lengthi16384eee");
+            let meta_info = MetaInfo::from_u8(&bencode).unwrap();
+            assert_eq!(meta_info.name(), "a.txt");
+            assert!(matches!(meta_info.info(), FileInfo::SingleFile(_)));
+        }
+
+        #[test]
+        fn default_name_03() {
+            // Nothing to derive a name from.
+            let bencode =
+                b"d4:infod6:lengthi1e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            assert_eq!(
+                MetaInfo::from_u8(bencode),
+                Err(MetaInfoError::MissingField("name"))
+            );
+            let bencode = b"d4:infod5:filesle12:piece lengthi16384e6:pieces0:ee";
+            assert_eq!(
+                MetaInfo::from_u8(bencode),
+                Err(MetaInfoError::MissingField("name"))
+            );
+            // Files which do not all lie in one directory.
+            for files in [
+                b"ld6:lengthi1e4:pathl3:top1:aeed6:lengthi1e4:pathl1:beee".as_slice(),
+                b"ld6:lengthi1e4:pathl3:top1:aeed6:lengthi1e4:pathl3:topeee",
+            ] {
+                let mut bencode = b"d4:infod5:files".to_vec();
+                bencode.extend_from_slice(files);
+                bencode
+                    .extend_from_slice(b"12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee");
+                assert_eq!(
+                    MetaInfo::from_u8(&bencode),
+                    Err(MetaInfoError::MissingField("name"))
+                );
+            }
+        }
+
+        #[test]
+        fn default_name_04() {
+            // The only top-level directory of a v2 file tree is not repeated in the paths either.
+            let mut bencode =
+                b"d4:infod9:file treed3:topd1:ad0:d6:lengthi3e11:pieces root32:".to_vec();
+            bencode.extend_from_slice(&[0xab; 32]);
+            bencode.extend_from_slice(b"ee1:bd0:d6:lengthi4e11:pieces root32:");
+            bencode.extend_from_slice(&[0xcd; 32]);
+            bencode.extend_from_slice(b"eeee12:meta versioni2e12:piece lengthi16384eee");
+            let meta_info = MetaInfo::from_u8(&bencode).unwrap();
+            assert_eq!(meta_info.name(), "top");
+            let paths: Vec<Vec<String>> = meta_info.files().into_iter().map(|x| x.path).collect();
+            assert_eq!(paths, vec![vec!["top", "a"], vec!["top", "b"]]);
+        }
+    }
+
+    mod info_bytes_test {
+        use super::*;
+
+        fn info() -> FileInfo {
+            return single_file_info("a", 1, 16384, &[b'a'; 20]);
+        }
+
+        #[test]
+        fn info_bytes_01() {
+            // Keys of `info` are out of order, and the bytes are kept as they are.
+            let bencode = b"d8:announce3:t/a4:infod4:name1:a6:lengthi1e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+            let meta_info = MetaInfo::from_u8(bencode).unwrap();
+            assert_eq!(meta_info.info_bytes(), &bencode[22..bencode.len() - 1]);
+            assert_eq!(
+                InfoHash(Sha1::digest(meta_info.info_bytes()).into()),
+                meta_info.info_hash()
+            );
+        }
+
+        #[test]
+        fn info_bytes_02() {
+            let meta_info = MetaInfo::new(info(), None);
+            assert_eq!(meta_info.info_bytes(), encode(&info().to_element()));
+        }
+    }
+
+    mod metadata_pieces_test {
+        use super::*;
+
+        fn meta_info(name_len: usize) -> MetaInfo {
+            let info = single_file_info(&"a".repeat(name_len), 1, 16384, &[b'a'; 20]);
+            return MetaInfo::new(info, None);
+        }
+
+        #[test]
+        fn metadata_pieces_01() {
+            let meta_info = meta_info(40000);
+            let size = meta_info.metadata_size();
+            assert_eq!(size, meta_info.info_bytes().len());
+            let pieces = meta_info.metadata_pieces();
+            assert_eq!(pieces.len(), size.div_ceil(16384));
+            assert!(pieces[..pieces.len() - 1].iter().all(|x| x.len() == 16384));
+            assert_eq!(pieces.last().unwrap().len(), size % 16384);
+            assert_eq!(
+                InfoHash(Sha1::digest(pieces.concat()).into()),
+                meta_info.info_hash()
+            );
+        }
+
+        #[test]
+        fn metadata_pieces_02() {
+            let meta_info = meta_info(1);
+            assert_eq!(meta_info.metadata_pieces(), vec![meta_info.info_bytes()]);
+
+            // Pad the name so that `info` is exactly two pieces.
+            let len = meta_info.metadata_size();
+            let meta_info = self::meta_info(1 + 2 * 16384 - len - 4);
+            assert_eq!(meta_info.metadata_size(), 2 * 16384);
+            assert_eq!(meta_info.metadata_pieces()[1].len(), 16384);
+        }
+    }
+
+    mod from_info_bytes_test {
+        use super::*;
+
+        const INFO: &[u8] =
+            b"d6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae";
+
+        fn magnet(info: &[u8]) -> PartialMetaInfo {
+            return PartialMetaInfo {
+                info_hash: InfoHash(Sha1::digest(info).into()),
+                name: Some("b".to_string()),
+                trackers: vec!["http://t/a".to_string(), "udp://t:80".to_string()],
+            };
+        }
+
+        #[test]
+        fn from_info_bytes_01() {
+            let magnet = self::magnet(INFO);
+            let meta_info = MetaInfo::from_info_bytes(INFO, &magnet).unwrap();
+            assert_eq!(meta_info.info_hash(), magnet.info_hash);
+            assert_eq!(meta_info.info_bytes(), INFO);
+            assert_eq!(meta_info.name(), "a");
+            assert_eq!(meta_info.total_size(), 1);
+            assert_eq!(meta_info.announce.as_deref(), Some("http://t/a"));
+            assert_eq!(meta_info.announce_list, Some(vec![magnet.trackers.clone()]));
+            assert_eq!(MetaInfo::from_magnet(&meta_info.to_magnet()).unwrap(), {
+                let mut x = magnet;
+                x.name = Some("a".to_string());
+                x
+            });
+        }
+
+        #[test]
+        fn from_info_bytes_02() {
+            // The hash does not match.
+            let mut magnet = self::magnet(INFO);
+            magnet.info_hash.0[0] ^= 1;
+            assert_eq!(MetaInfo::from_info_bytes(INFO, &magnet), None);
+
+            // Not an info dictionary.
+            let info = b"d4:name1:ae";
+            assert_eq!(MetaInfo::from_info_bytes(info, &self::magnet(info)), None);
+        }
+
+        #[test]
+        fn from_info_bytes_03() {
+            // Without `name` in `info`, `dn` of the magnet is used, and the hash is kept.
+            let info = b"d6:lengthi1e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae";
+            let mut magnet = self::magnet(info);
+            magnet.trackers.truncate(1);
+            let meta_info = MetaInfo::from_info_bytes(info, &magnet).unwrap();
+            assert_eq!(meta_info.name(), "b");
+            assert_eq!(meta_info.info_hash(), magnet.info_hash);
+            assert_eq!(meta_info.announce_list, None);
+        }
+    }
+
+    mod parse_fast_test {
+        use super::*;
+
+        fn helper(bencode: &[u8]) {
+            assert_eq!(
+                MetaInfo::parse_fast(bencode),
+                MetaInfo::from_u8_len_check(bencode),
+                "{}",
+                String::from_utf8_lossy(bencode)
+            );
+        }
+
+        #[test]
+        fn parse_fast_01() {
+            helper(b"d8:announce3:t/a4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee");
+            // Keys of `info` are out of order, and `info` is hashed as it is.
+            helper(b"d4:infod4:name1:a6:lengthi1e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae7:comment1:ce");
+            helper(b"d4:infod5:filesld6:lengthi1e4:pathl1:bee4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee");
+            assert!(MetaInfo::parse_fast(b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").is_some());
+        }
+
+        #[test]
+        fn parse_fast_02() {
+            for bencode in [
+                b"".as_slice(),
+                b"le",
+                b"d",
+                b"de",
+                b"d4:infoi1ee",
+                b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces19:aaaaaaaaaaaaaaaaaaaee",
+                b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:piecesi1eee",
+                b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces0:ee4:infodee",
+                b"d1:ai1e1:ai2e4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces0:ee",
+                b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces0:eei1e",
+                b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces0:ee ",
+                b"di1e1:a4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces0:ee",
+            ] {
+                assert_eq!(
+                    MetaInfo::parse_fast(bencode),
+                    None,
+                    "{}",
+                    String::from_utf8_lossy(bencode)
+                );
+                helper(bencode);
+            }
+        }
+
+        #[test]
+        fn parse_fast_03() {
+            // Every file is read from the borrowed `files` and must match the usual parser.
+            for files in [
+                b"d6:lengthi1e4:pathl1:b1:cee".as_slice(),
+                b"d6:lengthi1e4:pathl2:\xffbe10:path.utf-8l1:bee",
+                b"d6:lengthi1e4:pathl1:be10:path.utf-8l2:\xffbee",
+                b"d6:lengthi1e10:path.utf-8l1:bee",
+                b"d6:lengthi1e6:md5sum32:0123456789abcdef0123456789abcdef4:pathl1:bee",
+                b"d6:lengthi1e6:md5sumi1e4:pathl1:bee",
+                b"d6:lengthi1e4:pathl2:\xffbee",
+                b"d6:lengthi-1e4:pathl1:bee",
+                b"d6:length1:a4:pathl1:bee",
+                b"d4:pathl1:bee",
+                b"d6:lengthi1ee",
+                b"d6:lengthi1e4:pathli1eee",
+                b"i1e",
+            ] {
+                let mut bencode = b"d4:infod5:filesl".to_vec();
+                bencode.extend_from_slice(files);
+                bencode.extend_from_slice(
+                    b"e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+                );
+                helper(&bencode);
+            }
+
+            // Without a name, the first path component of the first file is the default name.
+            helper(b"d4:infod5:filesld6:lengthi1e4:pathl1:b1:ceee12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee");
+            let meta_info = MetaInfo::parse_fast(b"d4:infod5:filesld6:lengthi1e4:pathl1:b1:ceee12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+            assert_eq!(meta_info.name(), "b");
+        }
+    }
+
+    mod files_test {
+        use super::*;
+
+        fn entry(path: &[&str], length: u64, md5sum: Option<&str>) -> FileEntry {
+            return FileEntry {
+                path: path.iter().map(|x| x.to_string()).collect(),
+                length,
+                md5sum: md5sum.map(|x| x.to_string()),
+                is_padding: false,
+            };
+        }
+
+        #[test]
+        fn files_01() {
+            let meta_info = MetaInfo::from_u8(b"d4:infod6:lengthi5e6:md5sum32:0123456789abcdef0123456789abcdef4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+            assert_eq!(
+                meta_info.files(),
+                vec![entry(&["a"], 5, Some("0123456789abcdef0123456789abcdef"))]
+            );
+        }
+
+        #[test]
+        fn files_02() {
+            let meta_info = MetaInfo::from_u8(b"d4:infod5:filesld6:lengthi1e4:pathl1:b1:ceed6:lengthi2e4:pathl1:deee4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+            assert_eq!(
+                meta_info.files(),
+                vec![
+                    entry(&["a", "b", "c"], 1, None),
+                    entry(&["a", "d"], 2, None)
+                ]
+            );
+            assert_eq!(
+                meta_info.files().iter().map(|x| x.length).sum::<u64>(),
+                meta_info.total_size()
+            );
+        }
+
+        #[test]
+        fn files_03() {
+            let meta_info = MetaInfo::from_u8(b"d4:infod6:lengthi5e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+            assert_eq!(meta_info.file_offsets(), vec![0]);
+
+            let meta_info = MetaInfo::from_u8(b"d4:infod5:filesld6:lengthi3e4:pathl1:bee\
+                d6:lengthi0e4:pathl1:ceed6:lengthi20000e4:pathl1:deed6:lengthi1e4:pathl1:eeee\
+                4:name1:a12:piece lengthi16384e6:pieces40:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaee").unwrap();
+            let offsets = meta_info.file_offsets();
+            assert_eq!(offsets, vec![0, 3, 3, 20003]);
+            let layout = meta_info.file_piece_layout();
+            for (offset, range) in offsets.iter().zip(layout) {
+                assert_eq!(
+                    (range.start_piece * 16384 + range.first_piece_offset) as u64,
+                    *offset
+                );
+            }
+        }
+
+        #[test]
+        fn files_04() {
+            let info = multiple_file_info(&[usize::MAX, usize::MAX, 1], 16384, 1);
+            assert_eq!(
+                MetaInfo::new(info, None).file_offsets(),
+                vec![0, usize::MAX as u64, u64::MAX]
+            );
+        }
+    }
+
+    mod padding_test {
+        use super::*;
+
+        const BENCODE: &[u8] = b"d4:infod5:filesld6:lengthi3e4:pathl1:beed4:attr1:p6:lengthi16381e4:pathl4:.pad5:16381eed4:attr1:x6:lengthi2e4:pathl1:ceee4:name1:a12:piece lengthi16384e6:pieces40:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaee";
+
+        #[test]
+        fn padding_01() {
+            let meta_info = MetaInfo::from_u8(BENCODE).unwrap();
+            let FileInfo::MultipleFile(info) = meta_info.info() else {
+                panic!("not a multi-file torrent");
+            };
+            let files = info.files();
+            assert_eq!(files[0].attr(), None);
+            assert_eq!(files[1].attr(), Some("p"));
+            assert_eq!(files[2].attr(), Some("x"));
+            assert_eq!(
+                files.iter().map(|x| x.is_padding()).collect::<Vec<bool>>(),
+                vec![false, true, false]
+            );
+            assert_eq!(meta_info.total_size(), 16386);
+            assert_eq!(meta_info.content_size(), 5);
+            assert_eq!(meta_info.validate(), Ok(()));
+        }
+
+        #[test]
+        fn padding_02() {
+            let meta_info = MetaInfo::from_u8(BENCODE).unwrap();
+            let files = meta_info.files();
+            assert_eq!(
+                files.iter().map(|x| x.is_padding).collect::<Vec<bool>>(),
+                vec![false, true, false]
+            );
+            assert_eq!(meta_info.file_offsets(), vec![0, 3, 16384]);
+            let real: Vec<&[String]> = files
+                .iter()
+                .filter(|x| !x.is_padding)
+                .map(|x| x.path.as_slice())
+                .collect();
+            assert_eq!(real, vec![["a", "b"], ["a", "c"]]);
+        }
+
+        #[test]
+        fn padding_03() {
+            // `attr` survives re-encoding and the fast parser.
+            let meta_info = MetaInfo::from_u8(BENCODE).unwrap();
+            assert_eq!(
+                encode(&meta_info.info().to_element()),
+                meta_info.info_bytes()
+            );
+            assert_eq!(MetaInfo::parse_fast(BENCODE), Some(meta_info));
+        }
+
+        #[test]
+        fn padding_04() {
+            // Padding files of the same size share a path, which is not a collision.
+            let bencode = b"d4:infod5:filesld6:lengthi1e4:pathl1:beed4:attr1:p6:lengthi3e4:pathl4:.pad1:3eed6:lengthi1e4:pathl1:ceed4:attr1:p6:lengthi3e4:pathl4:.pad1:3eed6:lengthi1e4:pathl1:deee4:name1:a12:piece lengthi4e6:pieces60:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaee";
+            let options = ParseOptions {
+                reject_path_collisions: true,
+                ..Default::default()
+            };
+            assert!(MetaInfo::from_u8_with_options(bencode, &options).is_some());
+        }
     }
 }
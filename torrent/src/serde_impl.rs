@@ -0,0 +1,189 @@
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::meta_info::{
+    CommonFileInfo, FileInfo, FileTree, FileTreeFile, MetaInfo, MultipleFileInfoFile,
+};
+use crate::utils::hex_encode;
+
+/// Serialize as a map, leaving out `None` values.
+struct MapBuilder<M: SerializeMap>(M);
+
+impl<M: SerializeMap> MapBuilder<M> {
+    fn entry<V: Serialize + ?Sized>(&mut self, key: &str, val: &V) -> Result<(), M::Error> {
+        return self.0.serialize_entry(key, val);
+    }
+
+    fn optional<V: Serialize>(&mut self, key: &str, val: Option<V>) -> Result<(), M::Error> {
+        match val {
+            Some(x) => return self.0.serialize_entry(key, &x),
+            None => return Ok(()),
+        }
+    }
+
+    fn common_file_info(&mut self, info: &CommonFileInfo) -> Result<(), M::Error> {
+        self.entry("piece_length", &info.piece_length())?;
+        let pieces: Vec<String> = info.pieces().iter().map(|x| hex_encode(x)).collect();
+        self.entry("pieces", &pieces)?;
+        self.entry("private", &info.is_private())?;
+        return self.optional("source", info.source());
+    }
+}
+
+/// `name` is the decoded name, and `raw_name` holds the original bytes as hex only when they differ
+/// (non-UTF-8 names replaced by `name.utf-8`).
+/// Piece hashes are hex strings, and a single-file torrent has `length` where a multi-file one has `files`.
+impl Serialize for FileInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = MapBuilder(serializer.serialize_map(None)?);
+        map.entry("name", self.name())?;
+        if self.raw_name() != self.name().as_bytes() {
+            map.entry("raw_name", &hex_encode(self.raw_name()))?;
+        }
+        map.common_file_info(self.common_file_info())?;
+        match self {
+            FileInfo::SingleFile(x) => {
+                map.entry("length", &x.length())?;
+                map.optional("md5sum", x.md5sum())?;
+            }
+            FileInfo::MultipleFile(x) => map.entry("files", x.files())?,
+        }
+        return map.0.end();
+    }
+}
+
+impl Serialize for MultipleFileInfoFile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = MapBuilder(serializer.serialize_map(None)?);
+        map.entry("length", &self.length())?;
+        map.entry("path", self.path())?;
+        let raw_path: Vec<&[u8]> = self.raw_path().iter().map(|x| x.as_slice()).collect();
+        let path: Vec<&[u8]> = self.path().iter().map(|x| x.as_bytes()).collect();
+        if raw_path != path {
+            let raw_path: Vec<String> = raw_path.iter().map(|x| hex_encode(x)).collect();
+            map.entry("raw_path", &raw_path)?;
+        }
+        map.optional("md5sum", self.md5sum())?;
+        map.optional("attr", self.attr())?;
+        return map.0.end();
+    }
+}
+
+/// `pieces_root` is a hex string.
+impl Serialize for FileTreeFile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = MapBuilder(serializer.serialize_map(None)?);
+        map.entry("length", &self.length())?;
+        map.optional("pieces_root", self.pieces_root().map(|x| hex_encode(x)))?;
+        return map.0.end();
+    }
+}
+
+/// Directories are maps from names to sub-trees, and files are `{"length": .., "pieces_root": ..}`.
+impl Serialize for FileTree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            FileTree::File(x) => return x.serialize(serializer),
+            FileTree::Directory(x) => return serializer.collect_map(x),
+        }
+    }
+}
+
+/// Hashes are hex strings, and absent optional keys are left out.
+/// `piece_layers` maps hex `pieces root`s to lists of hex hashes.
+impl Serialize for MetaInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = MapBuilder(serializer.serialize_map(None)?);
+        map.entry("info_hash", &self.info_hash_hex())?;
+        map.optional("info_hash_v2", self.info_hash_v2().map(|x| hex_encode(&x)))?;
+        map.entry("info", self.info())?;
+        map.optional("announce", self.announce())?;
+        map.optional("announce_list", self.announce_list())?;
+        map.optional("creation_date", self.creation_date())?;
+        map.optional("comment", self.comment())?;
+        map.optional("created_by", self.created_by())?;
+        map.optional("encoding", self.encoding())?;
+        map.optional("nodes", self.nodes())?;
+        map.optional("url_list", self.url_list())?;
+        map.optional("http_seeds", self.http_seeds())?;
+        map.optional("meta_version", self.meta_version())?;
+        map.optional("file_tree", self.file_tree())?;
+        let piece_layers = self.piece_layers().map(|layers| {
+            return layers
+                .iter()
+                .map(|(root, hashes)| {
+                    let hashes: Vec<String> = hashes.iter().map(|x| hex_encode(x)).collect();
+                    return (hex_encode(root), hashes);
+                })
+                .collect::<std::collections::BTreeMap<_, _>>();
+        });
+        map.optional("piece_layers", piece_layers)?;
+        map.entry("total_size", &self.total_size())?;
+        return map.0.end();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod serde_test {
+        use super::*;
+
+        use crate::meta_info::tests::fixture_test::fixture;
+
+        #[test]
+        fn serde_01() {
+            let bencode = b"d8:announce3:t/a7:comment2:hi4:infod6:lengthi3e4:name3:a b12:piece lengthi16384e6:pieces20:\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xffee";
+            let meta_info = MetaInfo::from_u8(bencode).unwrap();
+            let json = serde_json::to_value(&meta_info).unwrap();
+            assert_eq!(json["info_hash"], meta_info.info_hash_hex());
+            assert_eq!(json["announce"], "t/a");
+            assert_eq!(json["comment"], "hi");
+            assert_eq!(json["total_size"], 3);
+            assert_eq!(json["info"]["name"], "a b");
+            assert_eq!(json["info"]["length"], 3);
+            assert_eq!(json["info"]["private"], false);
+            assert_eq!(json["info"]["pieces"][0], "ff".repeat(20));
+            for key in ["created_by", "info_hash_v2", "file_tree", "nodes"] {
+                assert!(json.get(key).is_none(), "{}", key);
+            }
+            assert!(json["info"].get("raw_name").is_none());
+        }
+
+        #[test]
+        fn serde_02() {
+            let meta_info = MetaInfo::from_u8(&fixture("multi_file.torrent")).unwrap();
+            let json = serde_json::to_value(&meta_info).unwrap();
+            let FileInfo::MultipleFile(info) = meta_info.info() else {
+                panic!("multi_file.torrent is not multi-file");
+            };
+            let files = json["info"]["files"].as_array().unwrap();
+            assert_eq!(files.len(), info.files().len());
+            for (file, expected) in files.iter().zip(info.files()) {
+                assert_eq!(file["length"], expected.length());
+                assert_eq!(file["path"], serde_json::json!(expected.path()));
+            }
+            assert!(json["info"].get("length").is_none());
+        }
+
+        #[test]
+        fn serde_03() {
+            let meta_info = MetaInfo::from_u8(&fixture("v2_only.torrent")).unwrap();
+            let json = serde_json::to_value(&meta_info).unwrap();
+            assert_eq!(json["meta_version"], 2);
+            assert_eq!(
+                json["info_hash_v2"],
+                hex_encode(&meta_info.info_hash_v2().unwrap())
+            );
+            let big = &json["file_tree"]["big.bin"];
+            assert_eq!(big["length"], 40000);
+            let root = big["pieces_root"].as_str().unwrap();
+            assert_eq!(root.len(), 64);
+            assert_eq!(json["piece_layers"][root].as_array().unwrap().len(), 3);
+            assert_eq!(json["file_tree"]["sub"]["empty.txt"]["length"], 0);
+            assert!(json["file_tree"]["sub"]["empty.txt"]
+                .get("pieces_root")
+                .is_none());
+        }
+    }
+}
@@ -0,0 +1,518 @@
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use bencode_decoder::*;
+
+use crate::compact::{decode_peers4, decode_peers6};
+use crate::info_hash::InfoHash;
+use crate::utils::url_encode;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AnnounceEvent {
+    Started,
+    Completed,
+    Stopped,
+}
+
+impl AnnounceEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnounceEvent::Started => "started",
+            AnnounceEvent::Completed => "completed",
+            AnnounceEvent::Stopped => "stopped",
+        }
+    }
+}
+
+/// Parameters of a HTTP tracker announce.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AnnounceRequest {
+    pub info_hash: InfoHash,
+    pub peer_id: [u8; 20],
+    pub port: u16,
+    pub uploaded: u64,
+    pub downloaded: u64,
+    pub left: u64,
+    pub compact: bool,
+    pub event: Option<AnnounceEvent>,
+    /// `tracker id` received from a previous announce. It must be sent back as it is.
+    pub tracker_id: Option<Vec<u8>>,
+}
+
+impl AnnounceRequest {
+    pub fn new(info_hash: InfoHash, peer_id: [u8; 20], port: u16, left: u64) -> Self {
+        return AnnounceRequest {
+            info_hash,
+            peer_id,
+            port,
+            uploaded: 0,
+            downloaded: 0,
+            left,
+            compact: true,
+            event: None,
+            tracker_id: None,
+        };
+    }
+
+    /// Remember `tracker id` of the response for subsequent announces.
+    /// If the response does not have it, the previous one is kept.
+    pub fn update_tracker_id(&mut self, response: &AnnounceResponse) {
+        if let Some(x) = &response.tracker_id {
+            self.tracker_id = Some(x.clone());
+        }
+    }
+
+    /// Build announce URL by appending query parameters to `announce`.
+    /// Raw bytes (`info_hash`, `peer_id` and `trackerid`) are percent-encoded as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `announce` - announce URL of tracker.
+    pub fn to_url(&self, announce: &str) -> String {
+        let mut url = announce.to_string();
+        url.push(if announce.contains('?') { '&' } else { '?' });
+        url.push_str(&format!(
+            "info_hash={}&peer_id={}&port={}&uploaded={}&downloaded={}&left={}&compact={}",
+            url_encode(self.info_hash.as_bytes()),
+            url_encode(&self.peer_id),
+            self.port,
+            self.uploaded,
+            self.downloaded,
+            self.left,
+            if self.compact { 1 } else { 0 },
+        ));
+        if let Some(event) = self.event {
+            url.push_str(&format!("&event={}", event.as_str()));
+        }
+        if let Some(tracker_id) = &self.tracker_id {
+            url.push_str(&format!("&trackerid={}", url_encode(tracker_id)));
+        }
+        return url;
+    }
+}
+
+/// Peer returned by a tracker.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Peer {
+    /// `peer id`, which only the non-compact format has.
+    pub peer_id: Option<Vec<u8>>,
+    pub addr: SocketAddr,
+}
+
+impl Peer {
+    /// Read a peer of the non-compact format, i.e. a dictionary with `ip`, `port` and `peer id`.
+    /// Return `None` if `ip` is not an IP address, e.g. a DNS name.
+    fn from_element(element: &Element) -> Option<Self> {
+        let dict = element.convert_to_ref_dict()?;
+        let ip = dict
+            .get(b"ip".as_slice())?
+            .convert_to_string()?
+            .parse::<IpAddr>()
+            .ok()?;
+        let port = dict.get(b"port".as_slice())?.convert_to_u16()?;
+        let peer_id = dict
+            .get(b"peer id".as_slice())
+            .and_then(|x| x.convert_to_ref_vec_u8())
+            .cloned();
+        return Some(Peer {
+            peer_id,
+            addr: SocketAddr::new(ip, port),
+        });
+    }
+}
+
+/// Read a compact `peers` string, 6 bytes per IPv4 peer (BEP 23).
+/// Returns `None` if the length is not a multiple of 6.
+pub fn parse_compact_peers(bytes: &[u8]) -> Option<Vec<SocketAddrV4>> {
+    return decode_peers4(bytes);
+}
+
+/// Read a compact `peers6` string, 18 bytes per IPv6 peer (BEP 7).
+/// Returns `None` if the length is not a multiple of 18.
+pub fn parse_compact_peers6(bytes: &[u8]) -> Option<Vec<SocketAddrV6>> {
+    return decode_peers6(bytes);
+}
+
+/// Read `peers`, which is either a list of dictionaries or a compact string.
+/// Peers which can not be read are skipped, and a malformed compact string gives no peers.
+fn parse_peers(element: &Element) -> Vec<Peer> {
+    if let Some(bytes) = element.convert_to_ref_vec_u8() {
+        return parse_compact_peers(bytes)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|addr| Peer {
+                peer_id: None,
+                addr: SocketAddr::V4(addr),
+            })
+            .collect();
+    }
+    return element
+        .iter_list()
+        .map(|peers| peers.filter_map(Peer::from_element).collect())
+        .unwrap_or_default();
+}
+
+/// Bencoded response of a HTTP tracker announce.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct AnnounceResponse {
+    pub failure_reason: Option<String>,
+    pub warning_message: Option<String>,
+    pub interval: Option<u64>,
+    pub min_interval: Option<u64>,
+    pub tracker_id: Option<Vec<u8>>,
+    /// Number of seeders.
+    pub complete: Option<u64>,
+    /// Number of leechers.
+    pub incomplete: Option<u64>,
+    /// Peers from `peers` followed by IPv6 ones from `peers6` (BEP 7).
+    pub peers: Vec<Peer>,
+}
+
+impl AnnounceResponse {
+    pub fn from_element(element: &Element) -> Option<Self> {
+        let dict = element.convert_to_ref_dict()?;
+
+        let mut ret = AnnounceResponse::default();
+        for (key, val) in dict {
+            match key.as_slice() {
+                b"failure reason" => {
+                    ret.failure_reason = val.convert_to_string();
+                }
+                b"warning message" => {
+                    ret.warning_message = val.convert_to_string();
+                }
+                b"interval" => {
+                    ret.interval = val.convert_to_u64();
+                }
+                b"min interval" => {
+                    ret.min_interval = val.convert_to_u64();
+                }
+                b"tracker id" => {
+                    ret.tracker_id = val.convert_to_ref_vec_u8().cloned();
+                }
+                b"complete" => {
+                    ret.complete = val.convert_to_u64();
+                }
+                b"incomplete" => {
+                    ret.incomplete = val.convert_to_u64();
+                }
+                b"peers" => {
+                    ret.peers = parse_peers(val);
+                }
+                // Keys are sorted, so `peers6` comes after `peers`.
+                b"peers6" => {
+                    let peers6 = val
+                        .convert_to_ref_vec_u8()
+                        .and_then(|x| parse_compact_peers6(x))
+                        .unwrap_or_default();
+                    ret.peers.extend(peers6.into_iter().map(|addr| Peer {
+                        peer_id: None,
+                        addr: SocketAddr::V6(addr),
+                    }));
+                }
+                _ => (),
+            }
+        }
+
+        return Some(ret);
+    }
+
+    pub fn from_bencode(bencode: &[u8]) -> Option<Self> {
+        let element = decode_len_check(bencode)?;
+        return AnnounceResponse::from_element(&element);
+    }
+}
+
+/// Statistics of a torrent in a scrape response.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ScrapeFile {
+    /// Number of seeders.
+    pub complete: Option<u64>,
+    /// Number of completed downloads ever reported.
+    pub downloaded: Option<u64>,
+    /// Number of leechers.
+    pub incomplete: Option<u64>,
+}
+
+/// Bencoded response of a HTTP tracker scrape (BEP 48).
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ScrapeResponse {
+    pub failure_reason: Option<String>,
+    /// Statistics keyed by raw info hash. Entries whose key is not 20 bytes are skipped.
+    pub files: BTreeMap<InfoHash, ScrapeFile>,
+}
+
+impl ScrapeResponse {
+    pub fn from_element(element: &Element) -> Option<Self> {
+        let dict = element.convert_to_ref_dict()?;
+
+        let mut ret = ScrapeResponse {
+            failure_reason: dict
+                .get(b"failure reason".as_slice())
+                .and_then(|x| x.convert_to_string()),
+            ..Default::default()
+        };
+        let files = dict
+            .get(b"files".as_slice())
+            .and_then(|x| x.convert_to_ref_dict());
+        for (key, val) in files.into_iter().flatten() {
+            let (Ok(info_hash), Some(stats)) = (
+                <[u8; 20]>::try_from(key.as_slice()),
+                val.convert_to_ref_dict(),
+            ) else {
+                continue;
+            };
+            let count = |key: &[u8]| stats.get(key).and_then(|x| x.convert_to_u64());
+            ret.files.insert(
+                InfoHash(info_hash),
+                ScrapeFile {
+                    complete: count(b"complete"),
+                    downloaded: count(b"downloaded"),
+                    incomplete: count(b"incomplete"),
+                },
+            );
+        }
+
+        return Some(ret);
+    }
+
+    pub fn from_bencode(bencode: &[u8]) -> Option<Self> {
+        let element = decode_len_check(bencode)?;
+        return ScrapeResponse::from_element(&element);
+    }
+
+    /// Statistics of the torrent with `info_hash`, if the tracker reported it.
+    pub fn get(&self, info_hash: &InfoHash) -> Option<&ScrapeFile> {
+        return self.files.get(info_hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod announce_response_test {
+        use super::*;
+
+        #[test]
+        fn announce_response_01() {
+            let response =
+                AnnounceResponse::from_bencode(b"d8:intervali1800e10:tracker id3:\x01a\xffe")
+                    .unwrap();
+            assert_eq!(response.interval, Some(1800));
+            assert_eq!(response.tracker_id, Some(vec![0x01, b'a', 0xff]));
+        }
+
+        #[test]
+        fn announce_response_02() {
+            let response = AnnounceResponse::from_bencode(b"d8:intervali1800ee").unwrap();
+            assert_eq!(response.tracker_id, None);
+        }
+
+        #[test]
+        fn announce_response_03() {
+            let response = AnnounceResponse::from_bencode(b"d14:failure reason4:oopse").unwrap();
+            assert_eq!(response.failure_reason, Some("oops".to_string()));
+        }
+
+        #[test]
+        fn announce_response_04() {
+            assert_eq!(AnnounceResponse::from_bencode(b"li1ee"), None);
+        }
+
+        #[test]
+        fn announce_response_05() {
+            let response = AnnounceResponse::from_bencode(
+                b"d8:completei5e10:incompletei2e8:intervali900e5:peers12:\x7f\x00\x00\x01\x1a\xe1\x0a\x00\x00\x02\x00\x50e",
+            )
+            .unwrap();
+            assert_eq!(response.complete, Some(5));
+            assert_eq!(response.incomplete, Some(2));
+            let addrs: Vec<SocketAddr> = response.peers.iter().map(|x| x.addr).collect();
+            assert_eq!(
+                addrs,
+                vec![
+                    "127.0.0.1:6881".parse().unwrap(),
+                    "10.0.0.2:80".parse().unwrap()
+                ]
+            );
+            assert!(response.peers.iter().all(|x| x.peer_id.is_none()));
+        }
+
+        #[test]
+        fn announce_response_06() {
+            let response = AnnounceResponse::from_bencode(
+                b"d5:peersld2:ip9:127.0.0.17:peer id3:abc4:porti6881eed2:ip11:example.com4:porti1eed2:ip3:::14:porti2eeee",
+            )
+            .unwrap();
+            assert_eq!(
+                response.peers,
+                vec![
+                    Peer {
+                        peer_id: Some(b"abc".to_vec()),
+                        addr: "127.0.0.1:6881".parse().unwrap(),
+                    },
+                    Peer {
+                        peer_id: None,
+                        addr: "[::1]:2".parse().unwrap(),
+                    },
+                ]
+            );
+        }
+
+        #[test]
+        fn announce_response_07() {
+            // A compact string of a wrong length gives no peers rather than failing the response.
+            let response =
+                AnnounceResponse::from_bencode(b"d8:intervali1e5:peers5:abcdee").unwrap();
+            assert_eq!(response.interval, Some(1));
+            assert!(response.peers.is_empty());
+        }
+
+        #[test]
+        fn announce_response_08() {
+            let mut bencode = b"d5:peers6:\x7f\x00\x00\x01\x1a\xe16:peers636:".to_vec();
+            bencode.extend_from_slice(&[0u8; 15]);
+            bencode.extend_from_slice(&[1, 0x1a, 0xe1]);
+            bencode.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+            bencode.extend_from_slice(&[0u8; 12]);
+            bencode.extend_from_slice(&[0x00, 0x50, b'e']);
+            let response = AnnounceResponse::from_bencode(&bencode).unwrap();
+            let addrs: Vec<SocketAddr> = response.peers.iter().map(|x| x.addr).collect();
+            assert_eq!(
+                addrs,
+                vec![
+                    "127.0.0.1:6881".parse().unwrap(),
+                    "[::1]:6881".parse().unwrap(),
+                    "[2001:db8::]:80".parse().unwrap()
+                ]
+            );
+
+            // `peers6` of a wrong length is ignored.
+            let response = AnnounceResponse::from_bencode(b"d6:peers65:abcdee").unwrap();
+            assert!(response.peers.is_empty());
+        }
+    }
+
+    mod announce_request_test {
+        use super::*;
+
+        fn request() -> AnnounceRequest {
+            return AnnounceRequest::new(InfoHash([0xab; 20]), *b"-RT0001-123456789012", 6881, 100);
+        }
+
+        #[test]
+        fn to_url_01() {
+            let url = request().to_url("http://tracker.example/announce");
+            assert_eq!(
+                url,
+                format!(
+                    "http://tracker.example/announce?info_hash={}&peer_id=-RT0001-123456789012&port=6881&uploaded=0&downloaded=0&left=100&compact=1",
+                    "%AB".repeat(20)
+                )
+            );
+        }
+
+        #[test]
+        fn to_url_02() {
+            let mut req = request();
+            req.event = Some(AnnounceEvent::Started);
+            let url = req.to_url("http://tracker.example/announce?key=1");
+            assert!(url.starts_with("http://tracker.example/announce?key=1&info_hash="));
+            assert!(url.ends_with("&event=started"));
+        }
+
+        #[test]
+        fn to_url_03() {
+            let response =
+                AnnounceResponse::from_bencode(b"d8:intervali1800e10:tracker id3:\x01a\xffe")
+                    .unwrap();
+            let mut req = request();
+            req.update_tracker_id(&response);
+            assert!(req
+                .to_url("http://tracker.example/announce")
+                .ends_with("&trackerid=%01a%FF"));
+
+            // A response without `tracker id` must not discard the previous one.
+            let response = AnnounceResponse::from_bencode(b"d8:intervali1800ee").unwrap();
+            req.update_tracker_id(&response);
+            assert_eq!(req.tracker_id, Some(vec![0x01, b'a', 0xff]));
+        }
+    }
+
+    mod scrape_response_test {
+        use super::*;
+
+        #[test]
+        fn scrape_response_01() {
+            let mut bencode = b"d5:filesd20:".to_vec();
+            bencode.extend_from_slice(&[0xff; 20]);
+            bencode.extend_from_slice(b"d8:completei5e10:downloadedi50e10:incompletei10ee20:");
+            bencode.extend_from_slice(&[0x00; 20]);
+            bencode.extend_from_slice(b"d8:completei1eeee");
+            let response = ScrapeResponse::from_bencode(&bencode).unwrap();
+            assert_eq!(response.files.len(), 2);
+            assert_eq!(
+                response.get(&InfoHash([0xff; 20])),
+                Some(&ScrapeFile {
+                    complete: Some(5),
+                    downloaded: Some(50),
+                    incomplete: Some(10),
+                })
+            );
+            assert_eq!(
+                response.get(&InfoHash([0x00; 20])),
+                Some(&ScrapeFile {
+                    complete: Some(1),
+                    ..Default::default()
+                })
+            );
+            assert_eq!(response.get(&InfoHash([0x01; 20])), None);
+        }
+
+        #[test]
+        fn scrape_response_02() {
+            // Keys which are not info hashes are skipped.
+            let response =
+                ScrapeResponse::from_bencode(b"d5:filesd3:abcd8:completei1eeee").unwrap();
+            assert!(response.files.is_empty());
+
+            let response = ScrapeResponse::from_bencode(b"d14:failure reason4:oopse").unwrap();
+            assert_eq!(response.failure_reason, Some("oops".to_string()));
+            assert_eq!(ScrapeResponse::from_bencode(b"li1ee"), None);
+        }
+    }
+
+    mod parse_compact_peers_test {
+        use super::*;
+
+        #[test]
+        fn parse_compact_peers_01() {
+            assert_eq!(
+                parse_compact_peers(b"\x7f\x00\x00\x01\x1a\xe1\x0a\x00\x00\x02\x00\x50"),
+                Some(vec![
+                    "127.0.0.1:6881".parse().unwrap(),
+                    "10.0.0.2:80".parse().unwrap()
+                ])
+            );
+            assert_eq!(parse_compact_peers(b""), Some(vec![]));
+            assert_eq!(parse_compact_peers(b"12345"), None);
+        }
+    }
+
+    mod parse_compact_peers6_test {
+        use super::*;
+
+        #[test]
+        fn parse_compact_peers6_01() {
+            let mut bytes = vec![0u8; 15];
+            bytes.extend_from_slice(b"\x01\x1a\xe1");
+            assert_eq!(
+                parse_compact_peers6(&bytes),
+                Some(vec!["[::1]:6881".parse().unwrap()])
+            );
+            assert_eq!(parse_compact_peers6(b""), Some(vec![]));
+            assert_eq!(parse_compact_peers6(&[0; 17]), None);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+/// Percent-encode bytes for a URL query component.
+/// Only unreserved characters (`A-Z`, `a-z`, `0-9`, `-`, `.`, `_`, `~`) are kept as they are.
+///
+/// # Arguments
+///
+/// * `bytes` - raw bytes to encode. They don't need to be UTF-8 (e.g. info-hash).
+///
+/// # Example
+///
+/// - `url_encode(b"a b")` returns `"a%20b"`.
+/// - `url_encode(&[0x12, 0xab])` returns `"%12%AB"`.
+pub fn url_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len() * 3);
+    for byte in bytes {
+        match *byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(*byte as char);
+            }
+            _ => {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    return encoded;
+}
+
+/// Decode a percent-encoded URL query component, where `+` also stands for a space.
+/// Return `None` if a `%` is not followed by two hex digits.
+///
+/// # Example
+///
+/// - `url_decode("a%20b+c")` returns `Some(b"a b c".to_vec())`.
+/// - `url_decode("%zz")` returns `None`.
+pub fn url_decode(s: &str) -> Option<Vec<u8>> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::<u8>::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'%' => {
+                let hex = core::str::from_utf8(bytes.get(idx + 1..idx + 3)?).ok()?;
+                decoded.push(*hex_decode(hex)?.first()?);
+                idx += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                idx += 1;
+            }
+            x => {
+                decoded.push(x);
+                idx += 1;
+            }
+        }
+    }
+    return Some(decoded);
+}
+
+/// Encode bytes as lowercase hex, two characters per byte.
+///
+/// # Example
+///
+/// - `hex_encode(&[0x12, 0xab])` returns `"12ab"`.
+pub fn hex_encode(bytes: &[u8]) -> String {
+    return bytes.iter().map(|x| format!("{:02x}", x)).collect();
+}
+
+/// Decode hex of either case. Return `None` on an odd length or a non-hex character.
+///
+/// # Example
+///
+/// - `hex_decode("12AB")` returns `Some(vec![0x12, 0xab])`.
+pub fn hex_decode(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|x| x.is_ascii_hexdigit()) {
+        return None;
+    }
+    return (0..s.len())
+        .step_by(2)
+        .map(|x| u8::from_str_radix(&s[x..x + 2], 16).ok())
+        .collect();
+}
+
+/// Encode bytes as unpadded uppercase base32 (RFC 4648), as used by magnet links.
+///
+/// # Example
+///
+/// - `base32_encode(b"foobar")` returns `"MZXW6YTBOI"`.
+pub fn base32_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut encoded = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    return encoded;
+}
+
+/// Decode unpadded base32 (RFC 4648) of either case.
+/// Return `None` on a character outside the alphabet or a length no encoding produces.
+///
+/// # Example
+///
+/// - `base32_decode("mzxw6ytboi")` returns `Some(b"foobar".to_vec())`.
+pub fn base32_decode(s: &str) -> Option<Vec<u8>> {
+    if matches!(s.len() % 8, 1 | 3 | 6) {
+        return None;
+    }
+
+    let mut decoded = Vec::<u8>::with_capacity(s.len() * 5 / 8);
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+    for ch in s.bytes() {
+        let val = match ch {
+            b'A'..=b'Z' => ch - b'A',
+            b'a'..=b'z' => ch - b'a',
+            b'2'..=b'7' => ch - b'2' + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | val as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    return Some(decoded);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod url_encode_test {
+        use super::*;
+
+        fn helper(input: &[u8], expect: &str) {
+            assert_eq!(url_encode(input), expect);
+        }
+
+        #[test]
+        fn url_encode_01() {
+            helper(b"", "");
+        }
+
+        #[test]
+        fn url_encode_02() {
+            helper(b"abcXYZ019-._~", "abcXYZ019-._~");
+        }
+
+        #[test]
+        fn url_encode_03() {
+            helper(b"a b&c=d", "a%20b%26c%3Dd");
+        }
+
+        #[test]
+        fn url_encode_04() {
+            helper(&[0x00, 0x12, 0xab, 0xff], "%00%12%AB%FF");
+        }
+    }
+
+    mod hex_encode_test {
+        use super::*;
+
+        #[test]
+        fn hex_encode_01() {
+            assert_eq!(hex_encode(b""), "");
+            assert_eq!(hex_encode(&[0x00, 0x12, 0xab, 0xff]), "0012abff");
+        }
+    }
+
+    mod base32_encode_test {
+        use super::*;
+
+        #[test]
+        fn base32_encode_01() {
+            // Test vectors from RFC 4648 without padding.
+            let cases: [(&[u8], &str); 7] = [
+                (b"", ""),
+                (b"f", "MY"),
+                (b"fo", "MZXQ"),
+                (b"foo", "MZXW6"),
+                (b"foob", "MZXW6YQ"),
+                (b"fooba", "MZXW6YTB"),
+                (b"foobar", "MZXW6YTBOI"),
+            ];
+            for (input, expect) in cases {
+                assert_eq!(base32_encode(input), expect);
+            }
+        }
+
+        #[test]
+        fn base32_encode_02() {
+            assert_eq!(base32_encode(&[0xff; 20]).len(), 32);
+            assert_eq!(base32_encode(&[0xff; 20]), "7".repeat(32));
+        }
+    }
+
+    mod url_decode_test {
+        use super::*;
+
+        #[test]
+        fn url_decode_01() {
+            assert_eq!(url_decode(""), Some(vec![]));
+            assert_eq!(url_decode("a%20b+c"), Some(b"a b c".to_vec()));
+            assert_eq!(url_decode("%00%ab%FF"), Some(vec![0x00, 0xab, 0xff]));
+        }
+
+        #[test]
+        fn url_decode_02() {
+            assert_eq!(url_decode("%zz"), None);
+            assert_eq!(url_decode("%a"), None);
+            assert_eq!(url_decode("%"), None);
+            assert_eq!(url_decode("%+1"), None);
+        }
+
+        #[test]
+        fn url_decode_03() {
+            let bytes: Vec<u8> = (0..=255).collect();
+            assert_eq!(url_decode(&url_encode(&bytes)), Some(bytes));
+        }
+    }
+
+    mod hex_decode_test {
+        use super::*;
+
+        #[test]
+        fn hex_decode_01() {
+            assert_eq!(hex_decode(""), Some(vec![]));
+            assert_eq!(hex_decode("0012abFF"), Some(vec![0x00, 0x12, 0xab, 0xff]));
+            assert_eq!(hex_decode("123"), None);
+            assert_eq!(hex_decode("zz"), None);
+            assert_eq!(hex_decode("+1"), None);
+        }
+    }
+
+    mod base32_decode_test {
+        use super::*;
+
+        #[test]
+        fn base32_decode_01() {
+            for input in [&b""[..], b"f", b"fo", b"foo", b"foob", b"fooba", b"foobar"] {
+                let encoded = base32_encode(input);
+                assert_eq!(base32_decode(&encoded).as_deref(), Some(input));
+                assert_eq!(
+                    base32_decode(&encoded.to_lowercase()).as_deref(),
+                    Some(input)
+                );
+            }
+        }
+
+        #[test]
+        fn base32_decode_02() {
+            assert_eq!(base32_decode("M"), None);
+            assert_eq!(base32_decode("MZX"), None);
+            assert_eq!(base32_decode("MZ0"), None);
+            assert_eq!(base32_decode("MZ=="), None);
+        }
+    }
+}